@@ -0,0 +1,265 @@
+use anyhow::{anyhow, Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+use xsd_test::{xml, ModelSchema, RustFmt};
+
+static USAGE: &str = "Usage: xsd-fiddle generate <SCHEMA> --out <DIR> [OPTIONS]
+
+Options:
+    -o, --out <DIR>        Directory to write the generated code to
+    -l, --lang <LANG>      Target language. Only `rust` is supported (default)
+        --serde            Derive Serialize and Deserialize on generated types
+    -i, --include <TYPE>   Only generate TYPE and the types it depends on.
+                           Can be given multiple times
+    -e, --exclude <TYPE>   Do not generate TYPE, which no generated type may
+                           depend on. Can be given multiple times
+        --check            Do not write anything, exit with 1 if the files in
+                           the output directory differ from the generated ones
+                           or include files that are not generated
+        --strict           Fail on any problem found in the schema
+    -h, --help             Print this message";
+
+struct Args {
+    schema: PathBuf,
+    out: PathBuf,
+    lang: String,
+    serde: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    check: bool,
+    strict: bool,
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        match args.next().as_deref() {
+            Some("generate") => (),
+            Some("-h") | Some("--help") => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            Some(other) => anyhow::bail!("Unknown command `{}`", other),
+            None => anyhow::bail!("Missing command"),
+        }
+
+        let mut schema = None;
+        let mut out = None;
+        let mut lang = String::from("rust");
+        let mut serde = false;
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        let mut check = false;
+        let mut strict = false;
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| anyhow!("Missing value for `{}`", arg))
+            };
+
+            match arg.as_str() {
+                "-o" | "--out" => out = Some(PathBuf::from(value()?)),
+                "-l" | "--lang" => lang = value()?,
+                "--serde" => serde = true,
+                "-i" | "--include" => include.push(value()?),
+                "-e" | "--exclude" => exclude.push(value()?),
+                "--check" => check = true,
+                "--strict" => strict = true,
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                }
+                s if s.starts_with('-') => anyhow::bail!("Unknown option `{}`", s),
+                s if schema.is_none() => schema = Some(PathBuf::from(s)),
+                s => anyhow::bail!("Unexpected argument `{}`", s),
+            }
+        }
+
+        Ok(Self {
+            schema: schema.ok_or_else(|| anyhow!("Missing <SCHEMA>"))?,
+            out: out.ok_or_else(|| anyhow!("Missing --out <DIR>"))?,
+            lang,
+            serde,
+            include,
+            exclude,
+            check,
+            strict,
+        })
+    }
+}
+
+fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            process::exit(2);
+        }
+    };
+
+    match run(args) {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            process::exit(2);
+        }
+    }
+}
+
+/// Returns false if running in check mode and the output is out of date.
+fn run(args: Args) -> Result<bool> {
+    let mut fmt = match args.lang.as_str() {
+        "rust" if args.serde => RustFmt::new().with_serde(),
+        "rust" => RustFmt::new(),
+        other => anyhow::bail!("Unsupported language `{}`", other),
+    };
+
+    let (mut models, report) =
+        ModelSchema::load_with_report(&mut xml::Loader::new(), &args.schema, args.strict)?;
+    for diagnostic in &report.diagnostics {
+        eprintln!("{}", diagnostic);
+    }
+
+    models.filter(&args.include, &args.exclude)?;
+    if models.uses_regex() {
+        eprintln!("Note: the generated code checks patterns with the `regex` crate");
+    }
+
+    if !args.check {
+        models.write_module(&mut fmt, &args.out)?;
+        return Ok(true);
+    }
+
+    let files = models.generate_files(&mut fmt)?;
+    let mut up_to_date = true;
+
+    // Left behind by types that were removed or renamed since.
+    for path in files_in(&args.out)? {
+        if !files.iter().any(|file| file.path == path) {
+            eprintln!("`{}` is not generated", args.out.join(path).display());
+            up_to_date = false;
+        }
+    }
+
+    for file in files {
+        let path = args.out.join(&file.path);
+        match fs::read_to_string(&path) {
+            Ok(contents) if contents == file.contents => (),
+            Ok(_) => {
+                eprintln!("`{}` is out of date", path.display());
+                up_to_date = false;
+            }
+            Err(_) => {
+                eprintln!("`{}` is missing", path.display());
+                up_to_date = false;
+            }
+        }
+    }
+
+    Ok(up_to_date)
+}
+
+/// All files below `dir`, relative to it. Empty if `dir` does not exist.
+fn files_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        let path = dir.join(&relative);
+        let entries =
+            fs::read_dir(&path).with_context(|| format!("Reading `{}`", path.display()))?;
+        for entry in entries {
+            let entry = entry.with_context(|| format!("Reading `{}`", path.display()))?;
+            let name = relative.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                dirs.push(name);
+            } else {
+                files.push(name);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn args(schema: &Path, out: &Path, check: bool) -> Args {
+        let mut args = vec![
+            String::from("generate"),
+            schema.display().to_string(),
+            String::from("--out"),
+            out.display().to_string(),
+        ];
+        if check {
+            args.push(String::from("--check"));
+        }
+        Args::parse(args.into_iter()).expect("Parsing arguments")
+    }
+
+    #[test]
+    fn check_generated_files() {
+        let dir = env::temp_dir().join(format!("xsd-fiddle-check-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let out = dir.join("gen");
+        fs::create_dir_all(&dir).expect("Creating directory");
+        let schema = dir.join("schema.xsd");
+        fs::write(
+            &schema,
+            r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Order">
+    <xs:sequence>
+      <xs:element name="Id" type="xs:int" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>"#,
+        )
+        .expect("Writing schema");
+
+        assert!(!run(args(&schema, &out, true)).expect("Checking"));
+        assert!(run(args(&schema, &out, false)).expect("Generating"));
+        assert!(run(args(&schema, &out, true)).expect("Checking"));
+
+        // Files of types that are no longer generated are stray.
+        fs::create_dir_all(out.join("old")).expect("Creating directory");
+        fs::write(out.join("old/invoice.rs"), "").expect("Writing file");
+        assert!(!run(args(&schema, &out, true)).expect("Checking"));
+        fs::remove_dir_all(out.join("old")).expect("Removing directory");
+        assert!(run(args(&schema, &out, true)).expect("Checking"));
+
+        fs::write(out.join("order.rs"), "").expect("Writing file");
+        assert!(!run(args(&schema, &out, true)).expect("Checking"));
+    }
+
+    #[test]
+    fn list_files_in_directories() {
+        let dir = env::temp_dir().join(format!("xsd-fiddle-files-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        assert!(files_in(&dir).expect("Listing").is_empty());
+
+        fs::create_dir_all(dir.join("b/c")).expect("Creating directory");
+        for name in &["mod.rs", "b/c/d.rs", "a.rs"] {
+            fs::write(dir.join(name), "").expect("Writing file");
+        }
+
+        assert_eq!(
+            files_in(&dir).expect("Listing"),
+            vec![
+                PathBuf::from("a.rs"),
+                PathBuf::from("b/c/d.rs"),
+                PathBuf::from("mod.rs"),
+            ]
+        );
+    }
+}
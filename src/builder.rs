@@ -0,0 +1,186 @@
+use crate::{xml, ModelSchema, NillableStyle, Primitive, RustFmt, TypeMapping};
+use anyhow::{anyhow, Context, Result};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+/// Generates Rust code from a schema at compile time.
+///
+/// Meant to be called from a `build.rs`, the result is a single file
+/// that can be included with `include!`:
+///
+/// ```ignore
+/// // build.rs
+/// xsd_test::Builder::new().schema("api.xsd").generate().unwrap();
+///
+/// // src/lib.rs
+/// mod api {
+///     include!(concat!(env!("OUT_DIR"), "/api.rs"));
+/// }
+/// ```
+///
+/// Types restricted by patterns check them with the `regex` crate,
+/// which the including crate must then depend on.
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    schema: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    file_name: Option<String>,
+    serde: bool,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    strict: bool,
+    types: Vec<(Primitive, TypeMapping)>,
+    nillable: Option<NillableStyle>,
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The XSD file to generate code from.
+    /// Schemas it includes or imports are loaded relative to it.
+    pub fn schema(mut self, path: impl AsRef<Path>) -> Self {
+        self.schema = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Directory to write the generated file to. Defaults to `OUT_DIR`.
+    pub fn out_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.out_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Name of the generated file. Defaults to the schema's file stem
+    /// with an `rs` extension.
+    pub fn file_name(mut self, name: impl Into<String>) -> Self {
+        self.file_name = Some(name.into());
+        self
+    }
+
+    /// Derive `Serialize` and `Deserialize` for all generated types.
+    pub fn with_serde(mut self) -> Self {
+        self.serde = true;
+        self
+    }
+
+    /// Only generate the given type and the types it depends on.
+    pub fn include(mut self, type_name: impl Into<String>) -> Self {
+        self.include.push(type_name.into());
+        self
+    }
+
+    /// Do not generate the given type. Generating fails if
+    /// any generated type depends on it.
+    pub fn exclude(mut self, type_name: impl Into<String>) -> Self {
+        self.exclude.push(type_name.into());
+        self
+    }
+
+    /// Writes `primitive` as another Rust type, see `RustFmt::map_type`.
+    pub fn map_type(mut self, primitive: Primitive, mapping: TypeMapping) -> Self {
+        self.types.push((primitive, mapping));
+        self
+    }
+
+    /// Writes fields that may be nil as `style`, see `RustFmt::nillable`.
+    pub fn nillable(mut self, style: NillableStyle) -> Self {
+        self.nillable = Some(style);
+        self
+    }
+
+    /// Fail on any problem found in the schema, instead of
+    /// reporting it as a cargo warning.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+
+    /// Generates the code and returns the path of the written file.
+    pub fn generate(self) -> Result<PathBuf> {
+        let schema = self
+            .schema
+            .ok_or_else(|| anyhow!("No schema given to Builder"))?;
+
+        let out_dir = match self.out_dir {
+            Some(dir) => dir,
+            None => env::var_os("OUT_DIR")
+                .map(PathBuf::from)
+                .ok_or_else(|| anyhow!("OUT_DIR is not set, use `Builder::out_dir`"))?,
+        };
+
+        let file_name = match self.file_name {
+            Some(name) => name,
+            None => schema
+                .file_stem()
+                .map(|stem| format!("{}.rs", stem.to_string_lossy()))
+                .ok_or_else(|| anyhow!("Invalid schema path `{}`", schema.display()))?,
+        };
+
+        println!("cargo:rerun-if-changed={}", schema.display());
+
+        // Files that failed to load are watched too, so fixing them reruns the build.
+        let mut loader = xml::Loader::new();
+        let result = ModelSchema::load_with_report(&mut loader, &schema, self.strict);
+        for path in loader.attempted().iter().skip(1) {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+
+        let (mut models, report) = result?;
+        for diagnostic in &report.diagnostics {
+            println!("cargo:warning={}", diagnostic);
+        }
+
+        models.filter(&self.include, &self.exclude)?;
+        if models.uses_regex() {
+            println!("cargo:warning=The generated code checks patterns with the `regex` crate, add it to the dependencies");
+        }
+
+        let mut fmt = if self.serde {
+            RustFmt::new().with_serde()
+        } else {
+            RustFmt::new()
+        };
+        for (primitive, mapping) in self.types {
+            fmt = fmt.map_type(primitive, mapping);
+        }
+        if let Some(style) = self.nillable {
+            fmt = fmt.nillable(style);
+        }
+
+        let contents = models.generate_combined_file(&mut fmt)?;
+
+        fs::create_dir_all(&out_dir)
+            .with_context(|| format!("Creating `{}`", out_dir.display()))?;
+        let path = out_dir.join(file_name);
+        fs::write(&path, contents).with_context(|| format!("Writing `{}`", path.display()))?;
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generate_single_file() {
+        let out_dir = env::temp_dir().join(format!("xsd-test-builder-{}", std::process::id()));
+
+        let path = Builder::new()
+            .schema(concat!(env!("CARGO_MANIFEST_DIR"), "/api.xsd"))
+            .out_dir(&out_dir)
+            .include("AccountingExportCreation")
+            .generate()
+            .expect("Generating");
+
+        assert_eq!(path, out_dir.join("api.rs"));
+
+        let contents = fs::read_to_string(&path).expect("Reading generated file");
+        assert!(contents.contains("pub struct AccountingExportCreation {"));
+        assert!(contents.contains("pub enum AccountingRecordType {"));
+        assert!(!contents.contains("use super"));
+    }
+}
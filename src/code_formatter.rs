@@ -1,4 +1,7 @@
-use crate::{ObjectImpl, TypeName};
+use crate::{
+    AbstractImpl, ChoiceImpl, EnumImpl, Implementation, ListImpl, NewtypeImpl, ObjectImpl,
+    TypeName, UnionImpl,
+};
 use std::fmt;
 
 pub trait CodeFormatter {
@@ -6,7 +9,91 @@ pub trait CodeFormatter {
     where
         W: fmt::Write;
 
+    fn write_enum_file<W>(&mut self, w: &mut W, enumeration: &EnumImpl) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    fn write_abstract_file<W>(&mut self, w: &mut W, abstract_impl: &AbstractImpl) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    fn write_choice_file<W>(&mut self, w: &mut W, choice: &ChoiceImpl) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    fn write_newtype_file<W>(&mut self, w: &mut W, newtype: &NewtypeImpl) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    fn write_list_file<W>(&mut self, w: &mut W, list: &ListImpl) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    fn write_union_file<W>(&mut self, w: &mut W, union: &UnionImpl) -> fmt::Result
+    where
+        W: fmt::Write;
+
     fn write_type<W>(&mut self, w: &mut W, p: &TypeName) -> fmt::Result
     where
         W: fmt::Write;
+
+    /// Name of the file an Implementation is written to.
+    fn file_name(&self, implementation: &Implementation) -> String;
+
+    /// Name of the file that ties all Implementation files together.
+    fn index_file_name(&self) -> String;
+
+    /// Writes all implementations into a single self-contained file.
+    fn write_combined_file<W>(
+        &mut self,
+        w: &mut W,
+        implementations: &[Implementation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    /// Writes the file declaring and exporting all implementations.
+    fn write_index_file<W>(&mut self, w: &mut W, implementations: &[Implementation]) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    /// Name of the module holding the implementations of a namespace.
+    fn module_name(&self, namespace: &str) -> String;
+
+    /// Writes the index file of a namespace module. Implementations in it
+    /// may refer to implementations of other namespaces.
+    fn write_namespace_index_file<W>(
+        &mut self,
+        w: &mut W,
+        implementations: &[Implementation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    /// Writes the index file declaring the namespace modules,
+    /// along with the implementations without a namespace.
+    fn write_root_index_file<W>(
+        &mut self,
+        w: &mut W,
+        modules: &[String],
+        implementations: &[Implementation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write;
+
+    /// Writes the file for any kind of Implementation.
+    fn write_file<W>(&mut self, w: &mut W, implementation: &Implementation) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match implementation {
+            Implementation::Enum(inner) => self.write_enum_file(w, inner),
+            Implementation::Object(inner) => self.write_impl_file(w, inner),
+            Implementation::Abstract(inner) => self.write_abstract_file(w, inner),
+            Implementation::Choice(inner) => self.write_choice_file(w, inner),
+            Implementation::Newtype(inner) => self.write_newtype_file(w, inner),
+            Implementation::List(inner) => self.write_list_file(w, inner),
+            Implementation::Union(inner) => self.write_union_file(w, inner),
+        }
+    }
 }
@@ -0,0 +1,147 @@
+use crate::{
+    xml::{self, Attribute, AttributeGroup, Group},
+    Diagnostic, Implementation, Report,
+};
+use std::collections::{HashMap, HashSet};
+
+/// State shared while creating the model from the xml schema.
+#[derive(Debug, Default)]
+pub struct Context {
+    pub report: Report,
+    /// Implementations created along the way, such as the enums of choices.
+    pub hoisted: Vec<Implementation>,
+    /// Names taken by types, so created implementations don't collide with them.
+    names: HashSet<String>,
+    /// Top level groups by name.
+    pub groups: HashMap<String, Group>,
+    pub attribute_groups: HashMap<String, AttributeGroup>,
+    /// Top level attributes by name.
+    pub attributes: HashMap<String, Attribute>,
+    /// Groups currently being inlined, to stop groups referencing themselves.
+    inlining: Vec<String>,
+}
+
+impl Context {
+    pub fn new<I>(names: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Self {
+            names: names.into_iter().collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Reserves `name`, or `name` suffixed by the first free number if taken.
+    pub fn unique_name(&mut self, name: &str) -> String {
+        let mut unique = name.to_string();
+        let mut i = 1;
+        while self.names.contains(&unique) {
+            i += 1;
+            unique = format!("{}{}", name, i);
+        }
+
+        self.names.insert(unique.clone());
+        unique
+    }
+
+    /// Starts inlining the group referenced by `reference` into `parent`.
+    /// Returns None and reports the problem if the reference can't be inlined.
+    /// Each returned group must be followed by a call to `leave_group`.
+    pub fn enter_group(&mut self, reference: &Group, parent: &str) -> Option<Group> {
+        let name = self.group_name(reference.reference.as_deref(), "group", parent)?;
+        match self.groups.get(&name).cloned() {
+            Some(group) => {
+                self.inlining.push(name);
+                Some(group)
+            }
+            None => self.unresolved("group", &name, parent),
+        }
+    }
+
+    /// Same as `enter_group`, for attribute groups.
+    pub fn enter_attribute_group(
+        &mut self,
+        reference: &AttributeGroup,
+        parent: &str,
+    ) -> Option<AttributeGroup> {
+        let name = self.group_name(reference.reference.as_deref(), "attribute group", parent)?;
+        match self.attribute_groups.get(&name).cloned() {
+            Some(group) => {
+                self.inlining.push(name);
+                Some(group)
+            }
+            None => self.unresolved("attribute group", &name, parent),
+        }
+    }
+
+    /// Replaces an attribute referring to a top level attribute by `ref`
+    /// with the name, type and values of the referenced attribute.
+    /// The use, and any values of its own, stay those of `attr`.
+    /// Returns None and reports the problem if the reference is unresolved.
+    pub fn resolve_attribute(&mut self, attr: Attribute, parent: &str) -> Option<Attribute> {
+        let reference = match &attr.reference {
+            Some(reference) => reference.clone(),
+            None => return Some(attr),
+        };
+
+        match self.attributes.get(xml::local_name(&reference)) {
+            Some(global) => Some(Attribute {
+                name: global.name.clone(),
+                reference: None,
+                kind: global.kind.clone(),
+                usage: attr.usage,
+                default: attr.default.or_else(|| global.default.clone()),
+                fixed: attr.fixed.or_else(|| global.fixed.clone()),
+            }),
+            None => self.unresolved("attribute", &reference, parent),
+        }
+    }
+
+    /// Ends inlining the group last entered.
+    pub fn leave_group(&mut self) {
+        self.inlining.pop();
+    }
+
+    /// The name of a referenced group, unless it is already being inlined.
+    fn group_name(&mut self, reference: Option<&str>, kind: &str, parent: &str) -> Option<String> {
+        let name = match reference {
+            Some(reference) => xml::local_name(reference).to_string(),
+            None => {
+                self.report.push(
+                    Diagnostic::warning(format!("Dropping {} without a `ref`", kind))
+                        .in_type(parent),
+                );
+                return None;
+            }
+        };
+
+        if self.inlining.contains(&name) {
+            self.report
+                .push(Diagnostic::error(format!("Cyclic {} `{}`", kind, name)).in_type(parent));
+            return None;
+        }
+
+        Some(name)
+    }
+
+    fn unresolved<T>(&mut self, kind: &str, name: &str, parent: &str) -> Option<T> {
+        self.report
+            .push(Diagnostic::warning(format!("Unresolved {} `{}`", kind, name)).in_type(parent));
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_names() {
+        let mut ctx = Context::new(vec![String::from("InvoiceChoice")]);
+
+        assert_eq!(ctx.unique_name("InvoiceChoice"), "InvoiceChoice2");
+        assert_eq!(ctx.unique_name("InvoiceChoice"), "InvoiceChoice3");
+        assert_eq!(ctx.unique_name("DebtorChoice"), "DebtorChoice");
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Warning => f.write_str("warning"),
+            Self::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// A problem found while building the model from a schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The type the problem was found in.
+    pub type_name: Option<String>,
+    /// The field of `type_name` the problem was found in.
+    pub field_name: Option<String>,
+    pub message: String,
+    /// Where in the source the problem is, if known.
+    pub location: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn warning(message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, message)
+    }
+
+    fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            type_name: None,
+            field_name: None,
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    pub fn in_type(mut self, type_name: impl Into<String>) -> Self {
+        self.type_name = Some(type_name.into());
+        self
+    }
+
+    pub fn in_field(mut self, field_name: impl Into<String>) -> Self {
+        self.field_name = Some(field_name.into());
+        self
+    }
+
+    pub fn at(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", self.severity)?;
+
+        match (&self.type_name, &self.field_name) {
+            (Some(t), Some(field)) => write!(f, "`{}.{}`: ", t, field)?,
+            (Some(t), None) => write!(f, "`{}`: ", t)?,
+            (None, Some(field)) => write!(f, "`{}`: ", field)?,
+            (None, None) => (),
+        }
+
+        f.write_str(&self.message)?;
+
+        if let Some(location) = &self.location {
+            write!(f, " ({})", location)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// All diagnostics found while building the model.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Report {
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Moves all diagnostics of `other` into this report.
+    pub fn append(&mut self, mut other: Report) {
+        self.diagnostics.append(&mut other.diagnostics);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    /// Turns all warnings into errors.
+    pub fn into_strict(mut self) -> Self {
+        self.diagnostics
+            .iter_mut()
+            .for_each(|d| d.severity = Severity::Error);
+        self
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, diagnostic) in self.diagnostics.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", diagnostic)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for Report {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_diagnostics() {
+        let mut report = Report::default();
+        report.push(
+            Diagnostic::warning("Dropping typeless field")
+                .in_type("WebhookEvent")
+                .in_field("Data"),
+        );
+        report.push(Diagnostic::error("Unknown base type `Foo`").at("api.xsd"));
+
+        assert!(report.has_errors());
+        assert_eq!(
+            report.to_string(),
+            "warning: `WebhookEvent.Data`: Dropping typeless field\n\
+             error: Unknown base type `Foo` (api.xsd)"
+        );
+
+        let strict = report.into_strict();
+        assert!(strict
+            .diagnostics
+            .iter()
+            .all(|d| d.severity == Severity::Error));
+    }
+}
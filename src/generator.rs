@@ -0,0 +1,311 @@
+use crate::{CodeFormatter, Implementation, ModelSchema};
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// A file created by a CodeFormatter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFile {
+    /// Path relative to the output directory.
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+impl ModelSchema {
+    /// Creates one file per Implementation and an index file
+    /// declaring them all, without touching the file system.
+    ///
+    /// If the implementations are from several namespaces, each
+    /// namespace gets a module of its own.
+    pub fn generate_files<F>(&self, fmt: &mut F) -> Result<Vec<GeneratedFile>>
+    where
+        F: CodeFormatter,
+    {
+        let mut by_namespace: BTreeMap<Option<&str>, Vec<Implementation>> = BTreeMap::new();
+        for imp in &self.implementations {
+            let ns = self.namespaces.get(imp.name()).map(String::as_str);
+            by_namespace.entry(ns).or_default().push(imp.clone());
+        }
+
+        let mut files = Vec::with_capacity(self.implementations.len() + by_namespace.len());
+        let mut paths = HashSet::new();
+
+        if by_namespace.len() < 2 {
+            let index = PathBuf::from(fmt.index_file_name());
+            write_implementations(
+                fmt,
+                Path::new(""),
+                &self.implementations,
+                &mut paths,
+                &mut files,
+            )?;
+            check_index(&index, &paths)?;
+
+            let mut contents = String::new();
+            fmt.write_index_file(&mut contents, &self.implementations)
+                .map_err(|_| anyhow!("Formatting `{}`", index.display()))?;
+            files.push(GeneratedFile {
+                path: index,
+                contents,
+            });
+
+            return Ok(files);
+        }
+
+        let root = by_namespace.remove(&None).unwrap_or_default();
+        let mut modules = Vec::with_capacity(by_namespace.len());
+
+        // Modules of the implementations without a namespace are taken too.
+        let mut taken = root
+            .iter()
+            .filter_map(|imp| {
+                let name = fmt.file_name(imp);
+                Path::new(&name)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().into_owned())
+            })
+            .collect::<HashSet<_>>();
+
+        for (ns, implementations) in by_namespace {
+            let ns = ns.unwrap_or_default();
+            let base = fmt.module_name(ns);
+            let mut module = base.clone();
+            for i in 2.. {
+                if taken.insert(module.clone()) {
+                    break;
+                }
+                module = format!("{}_{}", base, i);
+            }
+
+            let dir = PathBuf::from(&module);
+            let index = dir.join(fmt.index_file_name());
+            write_implementations(fmt, &dir, &implementations, &mut paths, &mut files)?;
+            check_index(&index, &paths)?;
+
+            let mut contents = String::new();
+            fmt.write_namespace_index_file(&mut contents, &implementations)
+                .map_err(|_| anyhow!("Formatting `{}`", index.display()))?;
+            files.push(GeneratedFile {
+                path: index,
+                contents,
+            });
+
+            modules.push(module);
+        }
+
+        let index = PathBuf::from(fmt.index_file_name());
+        write_implementations(fmt, Path::new(""), &root, &mut paths, &mut files)?;
+        check_index(&index, &paths)?;
+
+        let mut contents = String::new();
+        fmt.write_root_index_file(&mut contents, &modules, &root)
+            .map_err(|_| anyhow!("Formatting `{}`", index.display()))?;
+        files.push(GeneratedFile {
+            path: index,
+            contents,
+        });
+
+        Ok(files)
+    }
+
+    /// Creates a single file containing all implementations.
+    pub fn generate_combined_file<F>(&self, fmt: &mut F) -> Result<String>
+    where
+        F: CodeFormatter,
+    {
+        let mut contents = String::new();
+        fmt.write_combined_file(&mut contents, &self.implementations)
+            .map_err(|_| anyhow!("Formatting combined file"))?;
+
+        Ok(contents)
+    }
+
+    /// Writes all generated files into `dir`, creating it if needed.
+    pub fn write_module<F, P>(&self, fmt: &mut F, dir: P) -> Result<()>
+    where
+        F: CodeFormatter,
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir).with_context(|| format!("Creating `{}`", dir.display()))?;
+
+        for file in self.generate_files(fmt)? {
+            let path = dir.join(&file.path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Creating `{}`", parent.display()))?;
+            }
+            fs::write(&path, file.contents)
+                .with_context(|| format!("Writing `{}`", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates a file in `dir` for each Implementation.
+fn write_implementations<F>(
+    fmt: &mut F,
+    dir: &Path,
+    implementations: &[Implementation],
+    paths: &mut HashSet<PathBuf>,
+    files: &mut Vec<GeneratedFile>,
+) -> Result<()>
+where
+    F: CodeFormatter,
+{
+    for imp in implementations {
+        let path = dir.join(fmt.file_name(imp));
+        if !paths.insert(path.clone()) {
+            anyhow::bail!(
+                "`{}` would overwrite another implementation in `{}`",
+                imp.name(),
+                path.display()
+            );
+        }
+
+        let mut contents = String::new();
+        fmt.write_file(&mut contents, imp)
+            .map_err(|_| anyhow!("Formatting `{}`", imp.name()))?;
+        contents.push('\n');
+
+        files.push(GeneratedFile { path, contents });
+    }
+
+    Ok(())
+}
+
+fn check_index(index: &Path, paths: &HashSet<PathBuf>) -> Result<()> {
+    if paths.contains(index) {
+        anyhow::bail!("An implementation conflicts with `{}`", index.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        xml, EnumImpl, Field, Implementation, ModelSchema, ObjectImpl, Primitive, RustFmt, TypeName,
+    };
+    use std::{collections::HashMap, path::PathBuf};
+
+    #[test]
+    fn generate_module_files() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="AccountingExportCreation">
+    <xs:sequence>
+      <xs:element minOccurs="1" maxOccurs="1" name="Format" type="AccountingExportFormatType" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:simpleType name="AccountingExportFormatType">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="Unknown" />
+    </xs:restriction>
+  </xs:simpleType>
+</xs:schema>
+"#;
+
+        let schema: xml::Schema = xml::de(s.as_bytes()).expect("Deserializing");
+        let models = ModelSchema::create_from_xml(schema).expect("Creating ModelSchema");
+
+        let files = models
+            .generate_files(&mut RustFmt::new())
+            .expect("Generating files");
+
+        let paths = files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("accounting_export_creation.rs"),
+                PathBuf::from("accounting_export_format_type.rs"),
+                PathBuf::from("mod.rs"),
+            ]
+        );
+
+        assert!(files[0]
+            .contents
+            .starts_with("use super::{\n    AccountingExportFormatType,\n};"));
+        assert_eq!(
+            files[2].contents,
+            r#"mod accounting_export_creation;
+mod accounting_export_format_type;
+
+pub use accounting_export_creation::AccountingExportCreation;
+pub use accounting_export_format_type::AccountingExportFormatType;
+"#
+        );
+    }
+
+    #[test]
+    fn generate_namespace_modules() {
+        let models = ModelSchema {
+            implementations: vec![
+                Implementation::Object(ObjectImpl {
+                    name: "Order".into(),
+                    base: None,
+                    fields: vec![Field::new("Address", TypeName::object("Address"))],
+                    unordered: false,
+                    roots: Vec::new(),
+                }),
+                Implementation::Enum(EnumImpl {
+                    name: "Address".into(),
+                    roots: Vec::new(),
+                    base: TypeName::Primitive(Primitive::String),
+                    variants: vec!["Home".into()],
+                }),
+                Implementation::Enum(EnumImpl {
+                    name: "Local".into(),
+                    roots: Vec::new(),
+                    base: TypeName::Primitive(Primitive::String),
+                    variants: vec!["A".into()],
+                }),
+            ],
+            namespaces: vec![
+                ("Order".into(), "urn:shop:orders".into()),
+                ("Address".into(), "http://example.com/common/".into()),
+            ]
+            .into_iter()
+            .collect::<HashMap<_, _>>(),
+            roots: Vec::new(),
+        };
+
+        let files = models
+            .generate_files(&mut RustFmt::new())
+            .expect("Generating files");
+
+        let paths = files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("common/address.rs"),
+                PathBuf::from("common/mod.rs"),
+                PathBuf::from("orders/order.rs"),
+                PathBuf::from("orders/mod.rs"),
+                PathBuf::from("local.rs"),
+                PathBuf::from("mod.rs"),
+            ]
+        );
+
+        assert!(files[3]
+            .contents
+            .starts_with("#[allow(unused_imports)]\nuse super::*;\n\nmod order;"));
+        assert_eq!(
+            files[5].contents,
+            r#"pub mod common;
+pub mod orders;
+
+pub use common::*;
+pub use orders::*;
+
+mod local;
+
+pub use local::Local;
+"#
+        );
+    }
+}
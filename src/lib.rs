@@ -1,52 +1,827 @@
 use core::fmt;
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
 
+mod builder;
 mod code_formatter;
+mod context;
+mod diagnostic;
+mod generator;
+mod pattern;
 mod rust_fmt;
 pub mod xml;
 
+pub use builder::Builder;
 pub use code_formatter::CodeFormatter;
+pub use context::Context;
+pub use diagnostic::{Diagnostic, Report, Severity};
+pub use generator::GeneratedFile;
+pub use rust_fmt::{NillableStyle, RustFmt, TypeMapping};
 
 pub struct ModelSchema {
     pub implementations: Vec<Implementation>,
 
-    // Tempory
-    pub elements: Vec<xml::Element>,
+    /// The namespace of each implementation that has one, by name.
+    /// Names are unique across namespaces, see
+    /// `xml::Schema::disambiguate_type_names`.
+    pub namespaces: HashMap<String, String>,
+
+    /// The top level elements, which documents can have as their root.
+    pub roots: Vec<RootElement>,
 }
 
 impl ModelSchema {
+    /// Creates the model, ignoring any problems found on the way.
+    /// Use `create_from_xml_with_report` to find out about them.
     pub fn create_from_xml(xml: xml::Schema) -> Result<Self, anyhow::Error> {
+        Self::create_from_xml_with_report(xml, false).map(|(models, _)| models)
+    }
+
+    /// Creates the model along with a Report of the problems found.
+    /// Types that cannot be created are left out of the model.
+    ///
+    /// In `strict` mode all problems are errors, and any problem
+    /// fails the creation with the Report as the error.
+    pub fn create_from_xml_with_report(
+        mut xml: xml::Schema,
+        strict: bool,
+    ) -> Result<(Self, Report), anyhow::Error> {
         let mut implementations = Vec::new();
 
+        let mut ctx = Context::new(
+            xml.complex_types
+                .iter()
+                .map(|ct| ct.name.clone())
+                .chain(xml.simple_types.iter().map(|st| st.name.clone())),
+        );
+        xml.hoist_anonymous_types(&mut ctx);
+        xml.disambiguate_type_names(&mut ctx);
+        xml.resolve_element_references(&mut ctx.report);
+
         let complex_types = xml.complex_types;
         let simple_types = xml.simple_types;
         let elements = xml.elements;
-
-        for ct in complex_types
+        ctx.groups = xml
+            .groups
             .into_iter()
-            .filter(|t| !t.sequences.is_empty())
-        {
-            match ct.into_object_impl() {
-                Ok(object_impl) => implementations.push(Implementation::Object(object_impl)),
-                Err(err) => eprintln!("Failed to create ObjectImpl: {}", err),
+            .filter_map(|g| g.name.clone().map(|name| (name, g)))
+            .collect();
+        ctx.attribute_groups = xml
+            .attribute_groups
+            .into_iter()
+            .filter_map(|g| g.name.clone().map(|name| (name, g)))
+            .collect();
+        ctx.attributes = xml
+            .attributes
+            .into_iter()
+            .filter_map(|a| a.name.clone().map(|name| (name, a)))
+            .collect();
+
+        let abstract_types = complex_types
+            .iter()
+            .filter(|ct| ct.r#abstract)
+            .map(|ct| ct.name.clone())
+            .collect::<HashSet<_>>();
+
+        let bases = complex_types
+            .iter()
+            .map(|ct| {
+                let base = ct
+                    .extension()
+                    .and_then(|ext| ext.base_name())
+                    .map(String::from);
+                (ct.name.clone(), base)
+            })
+            .collect::<HashMap<_, _>>();
+
+        // Types wrapping a list of elements become array fields. They are
+        // still generated if other types extend them or documents have
+        // them as their root.
+        let always_generated = bases
+            .values()
+            .flatten()
+            .cloned()
+            .chain(
+                elements
+                    .iter()
+                    .filter_map(|el| el.kind.clone())
+                    .filter_map(|kind| TypeName::from(kind).import_statement().map(String::from)),
+            )
+            .collect::<HashSet<_>>();
+        let array_items = complex_types
+            .iter()
+            .filter_map(|ct| {
+                ct.array_item().map(|el| {
+                    let kind = el.kind.clone().expect("array item has a kind");
+                    (ct.name.clone(), (el.name.clone(), TypeName::from(kind)))
+                })
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut namespaces = complex_types
+            .iter()
+            .map(|ct| (&ct.name, &ct.namespace))
+            .chain(simple_types.iter().map(|st| (&st.name, &st.namespace)))
+            .filter_map(|(name, ns)| ns.clone().map(|ns| (name.clone(), ns)))
+            .collect::<HashMap<_, _>>();
+
+        let mut objects = Vec::new();
+        let mut wrappers = Vec::new();
+        let mut empty_types = HashSet::new();
+
+        for ct in complex_types {
+            if ct.is_empty() {
+                empty_types.insert(ct.name);
+                continue;
+            }
+
+            let name = ct.name.clone();
+            let start = ctx.hoisted.len();
+            match ct.into_object_impl(&mut ctx) {
+                Ok(object_impl)
+                    if array_items.contains_key(&name) && !always_generated.contains(&name) =>
+                {
+                    wrappers.push(object_impl)
+                }
+                Ok(mut object_impl) => {
+                    collapse_array_items(&mut object_impl.fields, &array_items);
+                    objects.push(object_impl)
+                }
+                Err(err) => ctx.report.push(
+                    Diagnostic::error(format!("Failed to create ObjectImpl: {}", err))
+                        .in_type(name.clone()),
+                ),
+            }
+
+            // Items of repeated groups are objects in the namespace of their type.
+            let (items, choices): (Vec<_>, Vec<_>) = ctx
+                .hoisted
+                .drain(start..)
+                .partition(|imp| matches!(imp, Implementation::Object(_)));
+            ctx.hoisted.extend(choices);
+            for item in items {
+                if let Implementation::Object(mut item) = item {
+                    if let Some(ns) = namespaces.get(&name).cloned() {
+                        namespaces.insert(item.name.clone(), ns);
+                    }
+                    collapse_array_items(&mut item.fields, &array_items);
+                    objects.push(item);
+                }
             }
         }
 
+        for hoisted in &mut ctx.hoisted {
+            if let Implementation::Choice(ref mut choice_impl) = hoisted {
+                collapse_array_items(&mut choice_impl.variants, &array_items);
+            }
+        }
+
+        let mut objects = inherit_fields(objects, &empty_types, &mut ctx.report);
+        flatten_choices(&mut objects, &mut ctx.hoisted, &mut ctx.report);
+        let wrappers = referenced_wrappers(wrappers, &objects, &ctx.hoisted);
+        objects.extend(wrappers);
+
+        // Abstract types are only used for inheriting fields,
+        // they are generated as an AbstractImpl instead.
+        implementations.extend(
+            objects
+                .into_iter()
+                .filter(|o| !abstract_types.contains(&o.name))
+                .map(Implementation::Object),
+        );
+
+        implementations.extend(
+            abstract_impls(&abstract_types, &bases)
+                .into_iter()
+                .map(Implementation::Abstract),
+        );
+
+        // Choices live in the namespace of the type they were declared in.
+        for imp in &implementations {
+            if let Implementation::Object(object) = imp {
+                if let Some(ns) = namespaces.get(&object.name).cloned() {
+                    object
+                        .fields
+                        .iter()
+                        .filter(|f| f.kind == FieldKind::Choice)
+                        .filter_map(|f| f.type_name.import_statement())
+                        .for_each(|choice| {
+                            namespaces.insert(choice.to_string(), ns.clone());
+                        });
+                }
+            }
+        }
+
+        implementations.append(&mut ctx.hoisted);
+
         for st in simple_types {
-            match st.into_enum_impl() {
-                Ok(enum_impl) => implementations.push(Implementation::Enum(enum_impl)),
-                Err(err) => eprintln!("Failed to create EnumImpl: {}", err),
+            let name = st.name.clone();
+            match st.into_implementation() {
+                Ok(implementation) => implementations.push(implementation),
+                Err(err) => ctx.report.push(
+                    Diagnostic::error(format!("Failed to create implementation: {}", err))
+                        .in_type(name),
+                ),
             }
         }
 
+        check_values(&mut implementations, &mut ctx.report);
+        translate_patterns(&mut implementations, &mut ctx.report);
+
+        let roots = root_elements(elements);
+        for imp in &mut implementations {
+            let type_name = TypeName::object(imp.name());
+            let names = roots
+                .iter()
+                .filter(|root| root.type_name == type_name)
+                .map(|root| root.name.clone())
+                .collect();
+
+            match imp {
+                Implementation::Enum(inner) => inner.roots = names,
+                Implementation::Object(inner) => inner.roots = names,
+                Implementation::Abstract(inner) => inner.roots = names,
+                Implementation::Newtype(inner) => inner.roots = names,
+                Implementation::List(inner) => inner.roots = names,
+                Implementation::Union(inner) => inner.roots = names,
+                Implementation::Choice(_) => (),
+            }
+        }
+
+        // Only generated types get functions reading and writing documents.
+        for root in roots
+            .iter()
+            .filter(|root| root.type_name.import_statement().is_none())
+        {
+            ctx.report.push(
+                Diagnostic::warning(format!(
+                    "Root element of built-in type `{}` has no generated type",
+                    root.type_name
+                ))
+                .in_type(root.name.as_str()),
+            );
+        }
+
         implementations.sort();
+        namespaces.retain(|name, _| implementations.iter().any(|imp| imp.name() == name));
+
+        let report = ctx.report;
+        if strict && !report.is_empty() {
+            return Err(report.into_strict().into());
+        }
+
+        Ok((
+            Self {
+                implementations,
+                namespaces,
+                roots,
+            },
+            report,
+        ))
+    }
+
+    /// Creates the model from the schema at `path` and every schema it
+    /// includes or imports. Problems loading them are part of the Report.
+    pub fn load_with_report<R>(
+        loader: &mut xml::Loader<R>,
+        path: impl AsRef<Path>,
+        strict: bool,
+    ) -> Result<(Self, Report), anyhow::Error>
+    where
+        R: xml::Resolver,
+    {
+        let (xml, mut report) = loader.load(path)?;
+        let (models, model_report) = Self::create_from_xml_with_report(xml, false)?;
+        report.append(model_report);
+
+        if strict && !report.is_empty() {
+            return Err(report.into_strict().into());
+        }
+
+        Ok((models, report))
+    }
+
+    /// Keeps the implementations named in `include` along with every
+    /// implementation they depend on, then removes those named in `exclude`.
+    /// An empty `include` keeps all implementations.
+    ///
+    /// Fails if a kept implementation depends on an excluded one,
+    /// as the generated code would not compile.
+    pub fn filter<S>(&mut self, include: &[S], exclude: &[S]) -> Result<(), anyhow::Error>
+    where
+        S: AsRef<str>,
+    {
+        if !include.is_empty() {
+            let mut keep = HashSet::new();
+            let mut queue = include.iter().map(|s| s.as_ref()).collect::<Vec<_>>();
+
+            while let Some(name) = queue.pop() {
+                if !keep.insert(name) {
+                    continue;
+                }
+
+                if let Some(imp) = self.implementations.iter().find(|i| i.name() == name) {
+                    queue.extend(imp.dependencies());
+                }
+            }
 
-        Ok(Self {
-            implementations,
-            elements,
+            let keep = keep.into_iter().map(String::from).collect::<HashSet<_>>();
+            self.implementations.retain(|i| keep.contains(i.name()));
+        }
+
+        let is_excluded = |name: &str| exclude.iter().any(|e| e.as_ref() == name);
+        for imp in self
+            .implementations
+            .iter()
+            .filter(|i| !is_excluded(i.name()))
+        {
+            if let Some(dependency) = imp.dependencies().into_iter().find(|d| is_excluded(d)) {
+                anyhow::bail!(
+                    "Cannot exclude `{}`, `{}` depends on it",
+                    dependency,
+                    imp.name()
+                );
+            }
+        }
+
+        self.implementations.retain(|i| !is_excluded(i.name()));
+        Ok(())
+    }
+
+    /// True if any generated type checks patterns, which it does with the
+    /// `regex` crate. The crate using the code must depend on it.
+    pub fn uses_regex(&self) -> bool {
+        self.implementations.iter().any(|imp| match imp {
+            Implementation::Newtype(newtype) => !newtype.facets.patterns.is_empty(),
+            _ => false,
         })
     }
 }
 
+/// Replaces fields typed as an array wrapper with an array of the
+/// wrapped item, remembering the name of the item element.
+/// Only fields of a single element are replaced, and items that are
+/// wrappers themselves are kept, as each wrapper element has a name.
+fn collapse_array_items(fields: &mut [Field], array_items: &HashMap<String, (String, TypeName)>) {
+    for field in fields {
+        let (item_name, item_type) = match field.type_name {
+            TypeName::Object(ref name) => match array_items.get(name) {
+                Some(item) => item,
+                None => continue,
+            },
+            _ => continue,
+        };
+
+        field.item_name = Some(item_name.clone());
+        field.type_name = TypeName::Array(Box::new(item_type.clone()));
+    }
+}
+
+/// The array wrappers still referred to by `objects` or choices after
+/// collapsing, such as items of other wrappers and types of repeated
+/// elements, along with the wrappers they refer to in turn.
+fn referenced_wrappers(
+    mut wrappers: Vec<ObjectImpl>,
+    objects: &[ObjectImpl],
+    hoisted: &[Implementation],
+) -> Vec<ObjectImpl> {
+    let choice_variants = hoisted.iter().flat_map(|imp| match imp {
+        Implementation::Choice(choice) => choice.variants.as_slice(),
+        _ => &[],
+    });
+    let mut referenced = objects
+        .iter()
+        .flat_map(|o| o.fields.iter())
+        .chain(choice_variants)
+        .filter_map(|f| f.type_name.import_statement())
+        .map(String::from)
+        .collect::<HashSet<_>>();
+
+    let mut res = Vec::new();
+    loop {
+        let (used, unused): (Vec<_>, Vec<_>) = wrappers
+            .into_iter()
+            .partition(|w| referenced.contains(&w.name));
+        if used.is_empty() {
+            return res;
+        }
+
+        referenced.extend(
+            used.iter()
+                .flat_map(|w| w.fields.iter())
+                .filter_map(|f| f.type_name.import_statement())
+                .map(String::from),
+        );
+        res.extend(used);
+        wrappers = unused;
+    }
+}
+
+/// Prepends the fields of all base types to the fields of each object,
+/// the top most base first.
+/// Objects extending themselves, directly or through other types, are dropped.
+fn inherit_fields(
+    objects: Vec<ObjectImpl>,
+    empty_types: &HashSet<String>,
+    report: &mut Report,
+) -> Vec<ObjectImpl> {
+    let by_name = objects
+        .iter()
+        .map(|o| (o.name.as_str(), o))
+        .collect::<HashMap<_, _>>();
+
+    let mut res = Vec::with_capacity(objects.len());
+
+    'objects: for object in &objects {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        visited.insert(object.name.as_str());
+
+        let mut base = object.base.as_deref();
+        while let Some(name) = base {
+            if !visited.insert(name) {
+                report.push(
+                    Diagnostic::error(format!("Cyclic base type `{}`", name))
+                        .in_type(object.name.clone()),
+                );
+                continue 'objects;
+            }
+
+            match by_name.get(name) {
+                Some(base_object) => {
+                    chain.push(base_object);
+                    base = base_object.base.as_deref();
+                }
+                None => {
+                    if !empty_types.contains(name) {
+                        report.push(
+                            Diagnostic::warning(format!("Unknown base type `{}`", name))
+                                .in_type(object.name.clone()),
+                        );
+                    }
+                    break;
+                }
+            }
+        }
+
+        let fields = chain
+            .iter()
+            .rev()
+            .flat_map(|o| o.fields.iter())
+            .chain(object.fields.iter())
+            .cloned()
+            .collect();
+
+        res.push(ObjectImpl {
+            name: object.name.clone(),
+            base: object.base.clone(),
+            fields,
+            unordered: object.unordered,
+            roots: Vec::new(),
+        });
+    }
+
+    res
+}
+
+/// Replaces the choice fields of objects with other element content by
+/// an optional field per variant. serde-xml-rs reads every child element
+/// of a struct with a `$value` field as that field, so a choice can only
+/// be the `$value` of an object without sibling elements.
+/// Choices no longer used by any object are removed from `hoisted`.
+fn flatten_choices(
+    objects: &mut [ObjectImpl],
+    hoisted: &mut Vec<Implementation>,
+    report: &mut Report,
+) {
+    let choices = hoisted
+        .iter()
+        .filter_map(|imp| match imp {
+            Implementation::Choice(choice) => Some((choice.name.clone(), choice.variants.clone())),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    for object in objects.iter_mut() {
+        let content = object
+            .fields
+            .iter()
+            .filter(|f| f.kind != FieldKind::Attribute)
+            .count();
+        if content < 2 {
+            continue;
+        }
+
+        let mut fields = Vec::with_capacity(object.fields.len());
+        for field in object.fields.drain(..) {
+            let variants = match field
+                .type_name
+                .import_statement()
+                .and_then(|n| choices.get(n))
+            {
+                Some(variants) if field.kind == FieldKind::Choice => variants,
+                _ => {
+                    fields.push(field);
+                    continue;
+                }
+            };
+
+            let message = match field.bounds {
+                Some(_) => {
+                    "Repeated choice with sibling elements is flattened into array fields, \
+                            the order of its alternatives is lost"
+                }
+                None => "Choice with sibling elements is flattened into optional fields",
+            };
+            report.push(
+                Diagnostic::warning(message)
+                    .in_type(object.name.as_str())
+                    .in_field(field.name.as_str()),
+            );
+
+            fields.extend(variants.iter().cloned().map(|mut variant| {
+                variant.required = false;
+                if let Some(choice_bounds) = field.bounds {
+                    match variant.bounds {
+                        Some(ref mut bounds) => {
+                            bounds.min = 0;
+                            bounds.max = bounds.max.zip(choice_bounds.max).map(|(a, b)| a * b);
+                        }
+                        None if variant.item_name.is_none() => {
+                            variant.type_name = TypeName::array(variant.type_name);
+                            variant.bounds = Some(Bounds {
+                                min: 0,
+                                max: choice_bounds.max,
+                            });
+                        }
+                        None => (),
+                    }
+                }
+                variant
+            }));
+        }
+        object.fields = fields;
+    }
+
+    hoisted.retain(|imp| match imp {
+        Implementation::Choice(choice) => objects.iter().any(|o| {
+            o.fields.iter().any(|f| {
+                f.kind == FieldKind::Choice
+                    && f.type_name.import_statement() == Some(choice.name.as_str())
+            })
+        }),
+        _ => true,
+    });
+}
+
+/// Drops the default and fixed values of fields that are not valid for
+/// their type, with a warning. Fields can't have both, so the default of
+/// a field with a fixed value is dropped as well.
+fn check_values(implementations: &mut [Implementation], report: &mut Report) {
+    let simple_types = implementations
+        .iter()
+        .filter_map(|imp| match imp {
+            Implementation::Enum(_) | Implementation::Newtype(_) => {
+                Some((imp.name().to_string(), imp.clone()))
+            }
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    for imp in implementations.iter_mut() {
+        let (name, fields) = match imp {
+            Implementation::Object(object) => (&object.name, &mut object.fields),
+            Implementation::Choice(choice) => (&choice.name, &mut choice.variants),
+            _ => continue,
+        };
+
+        for field in fields.iter_mut() {
+            if field.default.is_some() && field.fixed.is_some() {
+                report.push(
+                    Diagnostic::warning("Dropping default of field with a fixed value")
+                        .in_type(name.as_str())
+                        .in_field(field.name.as_str()),
+                );
+                field.default = None;
+            }
+
+            for (kind, value) in [("default", &mut field.default), ("fixed", &mut field.fixed)] {
+                let valid = match value {
+                    Some(v) => check_value(v, &field.type_name, &simple_types, 0),
+                    None => continue,
+                };
+
+                if let Err(err) = valid {
+                    report.push(
+                        Diagnostic::warning(format!("Dropping {} value: {}", kind, err))
+                            .in_type(name.as_str())
+                            .in_field(field.name.as_str()),
+                    );
+                    *value = None;
+                }
+            }
+        }
+    }
+}
+
+/// Translates the patterns of newtypes to the syntax of the `regex` crate.
+/// A value only has to match one of the patterns, so if any of them can't
+/// be translated all of them are dropped with a warning, as checking the
+/// others alone would reject valid values.
+fn translate_patterns(implementations: &mut [Implementation], report: &mut Report) {
+    for imp in implementations.iter_mut() {
+        let newtype = match imp {
+            Implementation::Newtype(newtype) => newtype,
+            _ => continue,
+        };
+
+        let translated = newtype
+            .facets
+            .patterns
+            .iter()
+            .map(|p| pattern::translate(p).map_err(|err| (p, err)))
+            .collect::<Result<Vec<_>, _>>();
+
+        match translated {
+            Ok(patterns) => newtype.facets.patterns = patterns,
+            Err((p, err)) => {
+                report.push(
+                    Diagnostic::warning(format!(
+                        "Dropping patterns, `{}` can't be checked: {}",
+                        p, err
+                    ))
+                    .in_type(newtype.name.as_str()),
+                );
+                newtype.facets.patterns.clear();
+            }
+        }
+    }
+}
+
+/// Checks that `value` is a value of `type_name`. Only the built-in types,
+/// enumerations and the bases of restricted types are checked.
+fn check_value(
+    value: &str,
+    type_name: &TypeName,
+    simple_types: &HashMap<String, Implementation>,
+    depth: usize,
+) -> Result<(), String> {
+    match type_name {
+        TypeName::Primitive(p) => check_primitive_value(value, p),
+        TypeName::Array(inner) => check_value(value, inner, simple_types, depth),
+        // Restricted types restricting each other would never end.
+        TypeName::Object(name) if depth < 8 => match simple_types.get(name) {
+            Some(Implementation::Enum(e)) if !e.variants.iter().any(|v| v == value) => {
+                Err(format!("`{}` is not a variant of `{}`", value, name))
+            }
+            Some(Implementation::Newtype(n)) => {
+                check_value(value, &n.base, simple_types, depth + 1)
+            }
+            _ => Ok(()),
+        },
+        TypeName::Object(_) => Ok(()),
+    }
+}
+
+/// Checks that `value` is in the lexical space of the built-in type `p`,
+/// as far as numbers and booleans go.
+pub(crate) fn check_primitive_value(value: &str, p: &Primitive) -> Result<(), String> {
+    // Rust doesn't parse integers with a leading `+`.
+    fn fits<T: std::str::FromStr>(value: &str) -> bool {
+        is_integer(value) && value.trim_start_matches('+').parse::<T>().is_ok()
+    }
+    // Unsigned integers may be written as `-0`.
+    fn fits_unsigned<T: std::str::FromStr>(value: &str) -> bool {
+        is_zero(value) || fits::<T>(value)
+    }
+
+    let trimmed = value.trim();
+    let negative = trimmed.starts_with('-');
+    let valid = match p {
+        Primitive::Bool => matches!(trimmed, "true" | "false" | "1" | "0"),
+        Primitive::Byte => fits::<i8>(trimmed),
+        Primitive::Short => fits::<i16>(trimmed),
+        Primitive::Int => fits::<i32>(trimmed),
+        Primitive::Long => fits::<i64>(trimmed),
+        Primitive::Integer => is_integer(trimmed),
+        Primitive::NonPositiveInteger => is_zero(trimmed) || (is_integer(trimmed) && negative),
+        Primitive::NegativeInteger => is_integer(trimmed) && negative && !is_zero(trimmed),
+        Primitive::UnsignedByte => fits_unsigned::<u8>(trimmed),
+        Primitive::UnsignedShort => fits_unsigned::<u16>(trimmed),
+        Primitive::UnsignedInt => fits_unsigned::<u32>(trimmed),
+        Primitive::UnsignedLong => fits_unsigned::<u64>(trimmed),
+        Primitive::NonNegativeInteger => is_zero(trimmed) || (is_integer(trimmed) && !negative),
+        Primitive::PositiveInteger => is_integer(trimmed) && !negative && !is_zero(trimmed),
+        Primitive::Float | Primitive::Double => is_float(trimmed),
+        Primitive::Decimal => is_decimal(trimmed),
+        _ => true,
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("`{}` is not a valid {}", value, p))
+    }
+}
+
+/// A number without its sign, which is optional.
+fn unsigned(value: &str) -> &str {
+    value.strip_prefix(['+', '-']).unwrap_or(value)
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// True if `value` is an `xs:integer`, of any size.
+fn is_integer(value: &str) -> bool {
+    is_digits(unsigned(value))
+}
+
+/// True if `value` is an integer of value zero, such as `-0` or `00`.
+fn is_zero(value: &str) -> bool {
+    is_integer(value) && unsigned(value).bytes().all(|b| b == b'0')
+}
+
+/// True if `value` is an `xs:decimal`, which has no exponent.
+fn is_decimal(value: &str) -> bool {
+    match unsigned(value).split_once('.') {
+        Some((int, fraction)) => {
+            (is_digits(int) || int.is_empty())
+                && (is_digits(fraction) || fraction.is_empty())
+                && !(int.is_empty() && fraction.is_empty())
+        }
+        None => is_integer(value),
+    }
+}
+
+/// True if `value` is an `xs:float` or `xs:double`, which spell
+/// infinity `INF` and may have an exponent.
+fn is_float(value: &str) -> bool {
+    if matches!(value, "INF" | "-INF" | "NaN") {
+        return true;
+    }
+
+    match value.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => is_decimal(mantissa) && is_integer(exponent),
+        None => is_decimal(value),
+    }
+}
+
+/// The top level elements. Typeless ones are of `anyType`.
+fn root_elements(elements: Vec<xml::Element>) -> Vec<RootElement> {
+    elements
+        .into_iter()
+        .map(|el| RootElement {
+            name: el.name,
+            type_name: TypeName::from(el.kind.unwrap_or(xml::Kind::AnyType)),
+        })
+        .collect()
+}
+
+/// Collects the concrete subtypes of each abstract type, including
+/// types extending it through other types.
+fn abstract_impls(
+    abstract_types: &HashSet<String>,
+    bases: &HashMap<String, Option<String>>,
+) -> Vec<AbstractImpl> {
+    let mut subtypes: HashMap<&str, Vec<String>> = abstract_types
+        .iter()
+        .map(|name| (name.as_str(), Vec::new()))
+        .collect();
+
+    for name in bases.keys().filter(|name| !abstract_types.contains(*name)) {
+        let mut visited = HashSet::new();
+        let mut base = bases.get(name).and_then(|b| b.as_deref());
+
+        while let Some(b) = base {
+            if !visited.insert(b) {
+                break;
+            }
+
+            if let Some(types) = subtypes.get_mut(b) {
+                types.push(name.clone());
+            }
+
+            base = bases.get(b).and_then(|b| b.as_deref());
+        }
+    }
+
+    subtypes
+        .into_iter()
+        .map(|(name, mut subtypes)| {
+            subtypes.sort();
+            AbstractImpl {
+                name: name.to_string(),
+                roots: Vec::new(),
+                subtypes,
+            }
+        })
+        .collect()
+}
+
 /// Represents a type.
 /// The base from which code is generated.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -64,6 +839,15 @@ impl TypeName {
             TypeName::Object(ref name) => Some(&name),
         }
     }
+
+    /// The Primitive of the type, or of its items if it's an array.
+    pub fn primitive(&self) -> Option<&Primitive> {
+        match self {
+            TypeName::Primitive(p) => Some(p),
+            TypeName::Array(ref inner) => inner.primitive(),
+            TypeName::Object(_) => None,
+        }
+    }
 }
 
 impl TypeName {
@@ -81,18 +865,60 @@ impl TypeName {
 
 impl From<xml::Kind> for TypeName {
     fn from(kind: xml::Kind) -> Self {
-        match kind {
-            xml::Kind::Boolean => Self::Primitive(Primitive::Bool),
-            xml::Kind::Int => Self::Primitive(Primitive::Int),
-            xml::Kind::Long => Self::Primitive(Primitive::Long),
-            xml::Kind::Double => Self::Primitive(Primitive::Double),
-            xml::Kind::String => Self::Primitive(Primitive::String),
-            xml::Kind::Base64Binary => Self::Object("Base64Binary".into()),
-            xml::Kind::Guid => Self::Object("Guid".into()),
-            xml::Kind::DateTime => Self::Object("DateTime".into()),
-            xml::Kind::Array(kind) => Self::Array(Box::new(Self::from(*kind))),
-            xml::Kind::Object(name) => Self::Object(name),
-        }
+        use xml::Kind;
+
+        let p = match kind {
+            Kind::Object(name) => return Self::Object(name.local),
+            Kind::Boolean => Primitive::Bool,
+            Kind::Int => Primitive::Int,
+            Kind::Long => Primitive::Long,
+            Kind::Double => Primitive::Double,
+            Kind::String => Primitive::String,
+            Kind::Base64Binary => Primitive::Base64Binary,
+            Kind::Guid => Primitive::Guid,
+            Kind::DateTime => Primitive::DateTime,
+            Kind::AnyType => Primitive::AnyType,
+            Kind::AnySimpleType => Primitive::AnySimpleType,
+            Kind::Decimal => Primitive::Decimal,
+            Kind::Float => Primitive::Float,
+            Kind::Duration => Primitive::Duration,
+            Kind::Time => Primitive::Time,
+            Kind::Date => Primitive::Date,
+            Kind::GYearMonth => Primitive::GYearMonth,
+            Kind::GYear => Primitive::GYear,
+            Kind::GMonthDay => Primitive::GMonthDay,
+            Kind::GDay => Primitive::GDay,
+            Kind::GMonth => Primitive::GMonth,
+            Kind::HexBinary => Primitive::HexBinary,
+            Kind::AnyUri => Primitive::AnyUri,
+            Kind::QName => Primitive::QName,
+            Kind::Notation => Primitive::Notation,
+            Kind::NormalizedString => Primitive::NormalizedString,
+            Kind::Token => Primitive::Token,
+            Kind::Language => Primitive::Language,
+            Kind::NmToken => Primitive::NmToken,
+            Kind::NmTokens => Primitive::NmTokens,
+            Kind::Name => Primitive::Name,
+            Kind::NcName => Primitive::NcName,
+            Kind::Id => Primitive::Id,
+            Kind::IdRef => Primitive::IdRef,
+            Kind::IdRefs => Primitive::IdRefs,
+            Kind::Entity => Primitive::Entity,
+            Kind::Entities => Primitive::Entities,
+            Kind::Integer => Primitive::Integer,
+            Kind::NonPositiveInteger => Primitive::NonPositiveInteger,
+            Kind::NegativeInteger => Primitive::NegativeInteger,
+            Kind::Short => Primitive::Short,
+            Kind::Byte => Primitive::Byte,
+            Kind::NonNegativeInteger => Primitive::NonNegativeInteger,
+            Kind::UnsignedLong => Primitive::UnsignedLong,
+            Kind::UnsignedInt => Primitive::UnsignedInt,
+            Kind::UnsignedShort => Primitive::UnsignedShort,
+            Kind::UnsignedByte => Primitive::UnsignedByte,
+            Kind::PositiveInteger => Primitive::PositiveInteger,
+        };
+
+        Self::Primitive(p)
     }
 }
 
@@ -112,31 +938,124 @@ impl fmt::Display for TypeName {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// The built-in types of XSD, and `guid`.
+/// See `xml::Kind` for how they relate to each other.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Primitive {
     Bool,
     Int,
     Long,
     Double,
     String,
+    Base64Binary,
+    Guid,
+    DateTime,
+    AnyType,
+    AnySimpleType,
+    Decimal,
+    Float,
+    Duration,
+    Time,
+    Date,
+    GYearMonth,
+    GYear,
+    GMonthDay,
+    GDay,
+    GMonth,
+    HexBinary,
+    AnyUri,
+    QName,
+    Notation,
+    NormalizedString,
+    Token,
+    Language,
+    NmToken,
+    NmTokens,
+    Name,
+    NcName,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+    Integer,
+    NonPositiveInteger,
+    NegativeInteger,
+    Short,
+    Byte,
+    NonNegativeInteger,
+    UnsignedLong,
+    UnsignedInt,
+    UnsignedShort,
+    UnsignedByte,
+    PositiveInteger,
 }
 
 impl fmt::Display for Primitive {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::Bool => f.write_str("boolean"),
-            Self::Int => f.write_str("int"),
-            Self::Long => f.write_str("long"),
-            Self::Double => f.write_str("double"),
-            Self::String => f.write_str("string"),
-        }
+        let name = match self {
+            Self::Bool => "boolean",
+            Self::Int => "int",
+            Self::Long => "long",
+            Self::Double => "double",
+            Self::String => "string",
+            Self::Base64Binary => "base64Binary",
+            Self::Guid => "guid",
+            Self::DateTime => "dateTime",
+            Self::AnyType => "anyType",
+            Self::AnySimpleType => "anySimpleType",
+            Self::Decimal => "decimal",
+            Self::Float => "float",
+            Self::Duration => "duration",
+            Self::Time => "time",
+            Self::Date => "date",
+            Self::GYearMonth => "gYearMonth",
+            Self::GYear => "gYear",
+            Self::GMonthDay => "gMonthDay",
+            Self::GDay => "gDay",
+            Self::GMonth => "gMonth",
+            Self::HexBinary => "hexBinary",
+            Self::AnyUri => "anyURI",
+            Self::QName => "QName",
+            Self::Notation => "NOTATION",
+            Self::NormalizedString => "normalizedString",
+            Self::Token => "token",
+            Self::Language => "language",
+            Self::NmToken => "NMTOKEN",
+            Self::NmTokens => "NMTOKENS",
+            Self::Name => "Name",
+            Self::NcName => "NCName",
+            Self::Id => "ID",
+            Self::IdRef => "IDREF",
+            Self::IdRefs => "IDREFS",
+            Self::Entity => "ENTITY",
+            Self::Entities => "ENTITIES",
+            Self::Integer => "integer",
+            Self::NonPositiveInteger => "nonPositiveInteger",
+            Self::NegativeInteger => "negativeInteger",
+            Self::Short => "short",
+            Self::Byte => "byte",
+            Self::NonNegativeInteger => "nonNegativeInteger",
+            Self::UnsignedLong => "unsignedLong",
+            Self::UnsignedInt => "unsignedInt",
+            Self::UnsignedShort => "unsignedShort",
+            Self::UnsignedByte => "unsignedByte",
+            Self::PositiveInteger => "positiveInteger",
+        };
+
+        f.write_str(name)
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Implementation {
     Enum(EnumImpl),
     Object(ObjectImpl),
+    Abstract(AbstractImpl),
+    Choice(ChoiceImpl),
+    Newtype(NewtypeImpl),
+    List(ListImpl),
+    Union(UnionImpl),
 }
 
 impl Implementation {
@@ -144,6 +1063,49 @@ impl Implementation {
         match self {
             Self::Enum(inner) => inner.name.as_str(),
             Self::Object(inner) => inner.name.as_str(),
+            Self::Abstract(inner) => inner.name.as_str(),
+            Self::Choice(inner) => inner.name.as_str(),
+            Self::Newtype(inner) => inner.name.as_str(),
+            Self::List(inner) => inner.name.as_str(),
+            Self::Union(inner) => inner.name.as_str(),
+        }
+    }
+
+    /// The top level elements of this type.
+    pub fn roots(&self) -> &[String] {
+        match self {
+            Self::Enum(inner) => &inner.roots,
+            Self::Object(inner) => &inner.roots,
+            Self::Abstract(inner) => &inner.roots,
+            Self::Choice(_) => &[],
+            Self::Newtype(inner) => &inner.roots,
+            Self::List(inner) => &inner.roots,
+            Self::Union(inner) => &inner.roots,
+        }
+    }
+
+    /// Names of the other implementations this one refers to.
+    pub fn dependencies(&self) -> Vec<&str> {
+        match self {
+            Self::Enum(_) => Vec::new(),
+            Self::Object(inner) => inner
+                .fields
+                .iter()
+                .filter_map(|f| f.type_name.import_statement())
+                .collect(),
+            Self::Abstract(inner) => inner.subtypes.iter().map(String::as_str).collect(),
+            Self::Choice(inner) => inner
+                .variants
+                .iter()
+                .filter_map(|f| f.type_name.import_statement())
+                .collect(),
+            Self::Newtype(inner) => inner.base.import_statement().into_iter().collect(),
+            Self::List(inner) => inner.item.import_statement().into_iter().collect(),
+            Self::Union(inner) => inner
+                .members
+                .iter()
+                .filter_map(TypeName::import_statement)
+                .collect(),
         }
     }
 }
@@ -165,24 +1127,153 @@ impl PartialOrd for Implementation {
 /// To implement other enums, such as ints or similar the kind would
 /// have to be contained in this struct and the variants written out
 /// in the serialize function of the enum impl.
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct EnumImpl {
     pub name: String,
+    /// The top level elements of this type.
+    pub roots: Vec<String>,
     pub base: TypeName,
     pub variants: Vec<String>,
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ObjectImpl {
     pub name: String,
+    /// The type this object extends. Its fields are already
+    /// included in `fields`.
+    pub base: Option<String>,
     pub fields: Vec<Field>,
+    /// True if the elements may appear in any order, as in an `xs:all`.
+    pub unordered: bool,
+    /// The top level elements of this type.
+    pub roots: Vec<String>,
 }
 
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+/// A top level element, which can be the root of a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RootElement {
+    pub name: String,
+    pub type_name: TypeName,
+}
+
+/// An abstract type, which can be any of its concrete subtypes.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AbstractImpl {
+    pub name: String,
+    /// The top level elements of this type.
+    pub roots: Vec<String>,
+    pub subtypes: Vec<String>,
+}
+
+/// Content that is exactly one of several alternatives.
+/// Each variant is the field the alternative would be on its own.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChoiceImpl {
+    pub name: String,
+    pub variants: Vec<Field>,
+}
+
+/// A restricted simple type, wrapping a value of its base type
+/// that satisfies the facets.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NewtypeImpl {
+    pub name: String,
+    /// The top level elements of this type.
+    pub roots: Vec<String>,
+    pub base: TypeName,
+    pub facets: Facets,
+}
+
+/// A simple type whose values are space separated items.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ListImpl {
+    pub name: String,
+    /// The top level elements of this type.
+    pub roots: Vec<String>,
+    pub item: TypeName,
+}
+
+/// A simple type whose values are those of any of its members,
+/// the first member accepting a value wins.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UnionImpl {
+    pub name: String,
+    /// The top level elements of this type.
+    pub roots: Vec<String>,
+    pub members: Vec<TypeName>,
+}
+
+/// Constraints on the values of a NewtypeImpl.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Facets {
+    /// The value must match one of these. Translated to the syntax of
+    /// the `regex` crate when the model is created.
+    pub patterns: Vec<String>,
+    pub length: Option<u64>,
+    pub min_length: Option<u64>,
+    pub max_length: Option<u64>,
+    /// Bounds as literals of the base type, such as `5` for `+5` or
+    /// `100000.0` for `1e5`. Bounds of types that aren't numbers
+    /// are kept as written.
+    pub min_inclusive: Option<String>,
+    pub max_inclusive: Option<String>,
+    pub total_digits: Option<u64>,
+    pub fraction_digits: Option<u64>,
+    pub white_space: Option<WhiteSpace>,
+}
+
+/// How white space in a value is normalized before it is checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WhiteSpace {
+    Preserve,
+    /// Tabs and line breaks are replaced by spaces.
+    Replace,
+    /// As Replace, and runs of spaces are collapsed into one and trimmed.
+    Collapse,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Field {
     pub name: String,
+    /// False if the field may be absent.
     pub required: bool,
+    /// True if the field may be present but nil, as in `xsi:nil="true"`.
+    pub nillable: bool,
+    /// The value of an attribute left out, or of an empty element.
+    pub default: Option<String>,
+    /// The only value the field may have.
+    pub fixed: Option<String>,
     pub type_name: TypeName,
+    /// Name of the element wrapping each item of an array field.
+    /// `None` if the items are not wrapped.
+    pub item_name: Option<String>,
+    /// Number of items allowed in an array of repeated elements.
+    pub bounds: Option<Bounds>,
+    pub kind: FieldKind,
+}
+
+/// How a field is represented in XML.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FieldKind {
+    Element,
+    Attribute,
+    /// Content that is one of the variants of a ChoiceImpl.
+    Choice,
+}
+
+/// The number of times a repeated element may occur.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Bounds {
+    pub min: u64,
+    /// `None` if unbounded.
+    pub max: Option<u64>,
+}
+
+impl Bounds {
+    /// The exact number of items, if min and max are the same.
+    pub fn exact(&self) -> Option<u64> {
+        self.max.filter(|max| *max == self.min)
+    }
 }
 
 impl Field {
@@ -190,7 +1281,13 @@ impl Field {
         Self {
             name: name.into(),
             required: true,
+            nillable: false,
+            default: None,
+            fixed: None,
             type_name: type_name.into(),
+            item_name: None,
+            bounds: None,
+            kind: FieldKind::Element,
         }
     }
 
@@ -198,6 +1295,41 @@ impl Field {
         self.required = false;
         self
     }
+
+    pub fn nillable(mut self) -> Self {
+        self.nillable = true;
+        self
+    }
+
+    pub fn with_default(mut self, value: impl Into<String>) -> Self {
+        self.default = Some(value.into());
+        self
+    }
+
+    pub fn with_fixed(mut self, value: impl Into<String>) -> Self {
+        self.fixed = Some(value.into());
+        self
+    }
+
+    pub fn attribute(mut self) -> Self {
+        self.kind = FieldKind::Attribute;
+        self
+    }
+
+    pub fn choice(mut self) -> Self {
+        self.kind = FieldKind::Choice;
+        self
+    }
+
+    pub fn bounded(mut self, min: u64, max: Option<u64>) -> Self {
+        self.bounds = Some(Bounds { min, max });
+        self
+    }
+
+    pub fn items_named(mut self, item_name: impl Into<String>) -> Self {
+        self.item_name = Some(item_name.into());
+        self
+    }
 }
 
 #[cfg(test)]
@@ -220,26 +1352,991 @@ mod tests {
             .iter()
             .for_each(|m| println!("{}", m.name()));
 
-        let mut fmt = super::rust_fmt::RustFmt;
+        let mut fmt = RustFmt::new().with_serde();
 
         println!("\n\n\n\n=====");
 
         models
             .implementations
             .iter()
-            .find(|m| m.name() == "CommonActionEvent")
-            .iter()
-            .for_each(|m| match m {
-                Implementation::Object(obj) => {
-                    let mut buf = String::new();
-                    fmt.write_impl_file(&mut buf, &obj)
-                        .expect("writing fmt file");
-                    println!("{}", buf);
-                }
-
-                _ => println!("NOT AN OBJECT wTF"),
+            .filter(|m| m.name() == "CommonActionEvent" || m.name() == "AccountingRecordType")
+            .for_each(|m| {
+                let mut buf = String::new();
+                fmt.write_file(&mut buf, m).expect("writing fmt file");
+                println!("{}\n", buf);
             });
 
         println!("Created {} models", models.implementations.len());
     }
+
+    #[test]
+    fn inherit_fields_from_extended_types() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Attribute" abstract="true" />
+  <xs:complexType name="EnumCompabilityDefault">
+    <xs:complexContent mixed="false">
+      <xs:extension base="Attribute">
+        <xs:sequence>
+          <xs:element minOccurs="1" maxOccurs="1" name="DefaultValue" type="xs:int" />
+        </xs:sequence>
+      </xs:extension>
+    </xs:complexContent>
+  </xs:complexType>
+  <xs:complexType name="NamedDefault">
+    <xs:complexContent mixed="false">
+      <xs:extension base="EnumCompabilityDefault">
+        <xs:sequence>
+          <xs:element minOccurs="0" maxOccurs="1" name="Name" type="xs:string" />
+        </xs:sequence>
+      </xs:extension>
+    </xs:complexContent>
+  </xs:complexType>
+  <xs:complexType name="BillectaDocsAdditionalInfo">
+    <xs:complexContent mixed="false">
+      <xs:extension base="Attribute" />
+    </xs:complexContent>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        let objects = models
+            .implementations
+            .iter()
+            .filter_map(|i| match i {
+                Implementation::Object(o) => Some(o),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(objects.len(), 3);
+
+        assert_eq!(objects[0].name, "BillectaDocsAdditionalInfo");
+        assert_eq!(objects[0].base.as_deref(), Some("Attribute"));
+        assert!(objects[0].fields.is_empty());
+
+        assert_eq!(objects[1].name, "EnumCompabilityDefault");
+        assert_eq!(
+            objects[1].fields,
+            vec![Field::new("DefaultValue", Primitive::Int)]
+        );
+
+        assert_eq!(objects[2].name, "NamedDefault");
+        assert_eq!(objects[2].base.as_deref(), Some("EnumCompabilityDefault"));
+        assert_eq!(
+            objects[2].fields,
+            vec![
+                Field::new("DefaultValue", Primitive::Int),
+                Field::new("Name", Primitive::String).optional(),
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_extension_with_own_content() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Base">
+    <xs:sequence>
+      <xs:element name="Id" type="xs:int" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Derived">
+    <xs:sequence>
+      <xs:element name="Note" type="xs:string" />
+    </xs:sequence>
+    <xs:attribute name="Version" type="xs:int" use="required" />
+    <xs:complexContent mixed="false">
+      <xs:extension base="Base">
+        <xs:sequence>
+          <xs:element name="Name" type="xs:string" />
+        </xs:sequence>
+      </xs:extension>
+    </xs:complexContent>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        match models
+            .implementations
+            .iter()
+            .find(|i| i.name() == "Derived")
+        {
+            Some(Implementation::Object(derived)) => assert_eq!(
+                derived.fields,
+                vec![
+                    Field::new("Id", Primitive::Int),
+                    Field::new("Name", Primitive::String),
+                    Field::new("Note", Primitive::String),
+                    Field::new("Version", Primitive::Int).attribute(),
+                ]
+            ),
+            other => panic!("Expected Derived, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn abstract_types_from_billecta_xsd() {
+        let xml_schema: xml::Schema =
+            xml::de(BILLECTA_XSD.as_bytes()).expect("Parsing Billecta XSD");
+
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        let converter = models
+            .implementations
+            .iter()
+            .find_map(|i| match i {
+                Implementation::Abstract(a) if a.name == "JsonConverter" => Some(a),
+                _ => None,
+            })
+            .expect("JsonConverter AbstractImpl");
+
+        assert!(converter
+            .subtypes
+            .contains(&String::from("StringToEnumConverter")));
+
+        assert!(models
+            .implementations
+            .iter()
+            .any(|i| matches!(i, Implementation::Object(o) if o.name == "StringToEnumConverter")));
+    }
+
+    #[test]
+    fn collapse_array_wrappers() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="AccountingExportCreation">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="1" name="BookKeepingTypesFilter" nillable="true" type="ArrayOfAccountingRecordType" />
+      <xs:element minOccurs="1" maxOccurs="1" name="Rows" type="TextRows" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="ArrayOfAccountingRecordType">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="unbounded" name="AccountingRecordType" type="AccountingRecordType" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="TextRows">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="unbounded" name="Row" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        assert_eq!(models.implementations.len(), 1);
+        let object = match &models.implementations[0] {
+            Implementation::Object(o) => o,
+            _ => panic!("Expected an ObjectImpl"),
+        };
+
+        assert_eq!(
+            object.fields,
+            vec![
+                Field::new(
+                    "BookKeepingTypesFilter",
+                    TypeName::array(TypeName::object("AccountingRecordType"))
+                )
+                .optional()
+                .nillable()
+                .items_named("AccountingRecordType"),
+                Field::new("Rows", TypeName::array(Primitive::String)).items_named("Row"),
+            ]
+        );
+    }
+
+    #[test]
+    fn keep_nested_and_repeated_array_wrappers() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Report">
+    <xs:sequence>
+      <xs:element name="Matrix" type="ArrayOfArrayOfString" />
+      <xs:element maxOccurs="unbounded" name="Page" type="ArrayOfInt" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="ArrayOfArrayOfString">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="unbounded" name="ArrayOfString" type="ArrayOfString" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="ArrayOfString">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="unbounded" name="string" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="ArrayOfInt">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="unbounded" name="int" type="xs:int" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        let fields = |name: &str| match models.implementations.iter().find(|i| i.name() == name) {
+            Some(Implementation::Object(object)) => object.fields.clone(),
+            other => panic!("Expected object {}, got {:?}", name, other),
+        };
+
+        // Each wrapper element inside another wrapper has items of its own.
+        assert_eq!(
+            fields("Report"),
+            vec![
+                Field::new("Matrix", TypeName::array(TypeName::object("ArrayOfString")))
+                    .items_named("ArrayOfString"),
+                Field::new("Page", TypeName::array(TypeName::object("ArrayOfInt")))
+                    .bounded(1, None),
+            ]
+        );
+        assert_eq!(
+            fields("ArrayOfString"),
+            vec![Field::new("string", TypeName::array(Primitive::String))
+                .optional()
+                .bounded(0, None)]
+        );
+        assert_eq!(
+            fields("ArrayOfInt"),
+            vec![Field::new("int", TypeName::array(Primitive::Int))
+                .optional()
+                .bounded(0, None)]
+        );
+        assert_eq!(models.implementations.len(), 3);
+    }
+
+    #[test]
+    fn keep_extended_and_root_array_wrappers() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="Codes" type="ArrayOfCode" />
+  <xs:complexType name="Letter">
+    <xs:sequence>
+      <xs:element name="Body" type="Lines" />
+      <xs:element name="Codes" type="ArrayOfCode" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Lines">
+    <xs:sequence>
+      <xs:element maxOccurs="unbounded" name="Line" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="NumberedLines">
+    <xs:complexContent mixed="false">
+      <xs:extension base="Lines">
+        <xs:attribute name="Start" type="xs:int" />
+      </xs:extension>
+    </xs:complexContent>
+  </xs:complexType>
+  <xs:complexType name="ArrayOfCode">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="unbounded" name="Code" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        let fields = |name: &str| match models.implementations.iter().find(|i| i.name() == name) {
+            Some(Implementation::Object(object)) => object.fields.clone(),
+            other => panic!("Expected object {}, got {:?}", name, other),
+        };
+
+        assert_eq!(
+            fields("Letter"),
+            vec![
+                Field::new("Body", TypeName::array(Primitive::String)).items_named("Line"),
+                Field::new("Codes", TypeName::array(Primitive::String)).items_named("Code"),
+            ]
+        );
+        assert_eq!(
+            fields("Lines"),
+            vec![Field::new("Line", TypeName::array(Primitive::String)).bounded(1, None)]
+        );
+        assert_eq!(
+            fields("NumberedLines"),
+            vec![
+                Field::new("Line", TypeName::array(Primitive::String)).bounded(1, None),
+                Field::new("Start", Primitive::Int).attribute().optional(),
+            ]
+        );
+        assert_eq!(
+            models
+                .implementations
+                .iter()
+                .find(|i| i.name() == "ArrayOfCode")
+                .map(|i| i.roots()),
+            Some(&[String::from("Codes")][..])
+        );
+        assert_eq!(models.implementations.len(), 4);
+    }
+
+    #[test]
+    fn repeated_elements_become_arrays() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Invoice">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="unbounded" name="Record" type="InvoiceRecord" />
+      <xs:element minOccurs="2" maxOccurs="2" name="Address" type="xs:string" />
+      <xs:element minOccurs="1" maxOccurs="5" name="Reference" type="xs:string" />
+      <xs:element name="Amount" type="xs:double" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        let object = match &models.implementations[0] {
+            Implementation::Object(o) => o,
+            _ => panic!("Expected an ObjectImpl"),
+        };
+
+        assert_eq!(
+            object.fields,
+            vec![
+                Field::new("Record", TypeName::array(TypeName::object("InvoiceRecord")))
+                    .optional()
+                    .bounded(0, None),
+                Field::new("Address", TypeName::array(Primitive::String)).bounded(2, Some(2)),
+                Field::new("Reference", TypeName::array(Primitive::String)).bounded(1, Some(5)),
+                Field::new("Amount", Primitive::Double),
+            ]
+        );
+    }
+
+    #[test]
+    fn root_elements() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="Invoice" type="Invoice" />
+  <xs:element name="CreditInvoice" type="Invoice" />
+  <xs:element name="Note" type="xs:string" />
+  <xs:element name="Anything" />
+  <xs:complexType name="Invoice">
+    <xs:sequence>
+      <xs:element name="Amount" type="xs:double" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let (models, report) = ModelSchema::create_from_xml_with_report(xml_schema, false)
+            .expect("Creating ModelSchema");
+
+        assert_eq!(
+            models.roots,
+            vec![
+                RootElement {
+                    name: "Invoice".into(),
+                    type_name: TypeName::object("Invoice"),
+                },
+                RootElement {
+                    name: "CreditInvoice".into(),
+                    type_name: TypeName::object("Invoice"),
+                },
+                RootElement {
+                    name: "Note".into(),
+                    type_name: TypeName::Primitive(Primitive::String),
+                },
+                RootElement {
+                    name: "Anything".into(),
+                    type_name: TypeName::Primitive(Primitive::AnyType),
+                },
+            ]
+        );
+
+        assert_eq!(
+            models.implementations[0].roots(),
+            ["Invoice", "CreditInvoice"]
+        );
+
+        assert_eq!(report.diagnostics.len(), 2);
+        assert_eq!(
+            report.diagnostics[0].message,
+            "Root element of built-in type `string` has no generated type"
+        );
+        assert_eq!(report.diagnostics[0].type_name.as_deref(), Some("Note"));
+        assert_eq!(
+            report.diagnostics[1].message,
+            "Root element of built-in type `anyType` has no generated type"
+        );
+    }
+
+    #[test]
+    fn check_default_and_fixed_values() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="Currency">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="SEK" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:complexType name="Payment">
+    <xs:sequence>
+      <xs:element name="Amount" type="xs:double" default="1.5" />
+      <xs:element name="Count" type="xs:unsignedByte" default="300" />
+      <xs:element name="Currency" type="Currency" default="SEK" />
+      <xs:element name="Other" type="Currency" default="USD" fixed="SEK" />
+    </xs:sequence>
+    <xs:attribute name="Version" type="xs:int" fixed="2" />
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let (models, report) = ModelSchema::create_from_xml_with_report(xml_schema, false)
+            .expect("Creating ModelSchema");
+
+        let object = models
+            .implementations
+            .iter()
+            .find_map(|imp| match imp {
+                Implementation::Object(o) => Some(o),
+                _ => None,
+            })
+            .expect("An ObjectImpl");
+
+        assert_eq!(
+            object.fields,
+            vec![
+                Field::new("Amount", Primitive::Double).with_default("1.5"),
+                Field::new("Count", Primitive::UnsignedByte),
+                Field::new("Currency", TypeName::object("Currency")).with_default("SEK"),
+                Field::new("Other", TypeName::object("Currency")).with_fixed("SEK"),
+                Field::new("Version", Primitive::Int)
+                    .attribute()
+                    .optional()
+                    .with_fixed("2"),
+            ]
+        );
+        assert_eq!(
+            report.diagnostics,
+            vec![
+                Diagnostic::warning("Dropping default value: `300` is not a valid unsignedByte")
+                    .in_type("Payment")
+                    .in_field("Count"),
+                Diagnostic::warning("Dropping default of field with a fixed value")
+                    .in_type("Payment")
+                    .in_field("Other"),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_lexical_values() {
+        let valid = |value: &str, p: Primitive| check_primitive_value(value, &p).is_ok();
+
+        assert!(valid("+5", Primitive::Int));
+        assert!(!valid("++5", Primitive::Int));
+        assert!(!valid("5.0", Primitive::Int));
+        assert!(!valid("128", Primitive::Byte));
+        assert!(valid("-0", Primitive::UnsignedByte));
+        assert!(!valid("-1", Primitive::UnsignedInt));
+
+        assert!(valid("123456789012345678901234567890", Primitive::Integer));
+        assert!(valid(
+            "-123456789012345678901234567890",
+            Primitive::NonPositiveInteger
+        ));
+        assert!(valid("0", Primitive::NonPositiveInteger));
+        assert!(!valid("1", Primitive::NonPositiveInteger));
+        assert!(!valid("-0", Primitive::NegativeInteger));
+        assert!(valid(
+            "123456789012345678901234567890",
+            Primitive::PositiveInteger
+        ));
+        assert!(!valid("00", Primitive::PositiveInteger));
+        assert!(!valid("1e3", Primitive::Integer));
+
+        assert!(valid("-1.5", Primitive::Decimal));
+        assert!(valid("1.", Primitive::Decimal));
+        assert!(valid(".5", Primitive::Decimal));
+        assert!(!valid(".", Primitive::Decimal));
+        assert!(!valid("1e3", Primitive::Decimal));
+        assert!(!valid("INF", Primitive::Decimal));
+        assert!(!valid("+-1", Primitive::Decimal));
+
+        assert!(valid("INF", Primitive::Double));
+        assert!(valid("-INF", Primitive::Double));
+        assert!(valid("NaN", Primitive::Float));
+        assert!(valid("-1.5E-3", Primitive::Double));
+        assert!(valid("1e+3", Primitive::Float));
+        assert!(!valid("inf", Primitive::Double));
+        assert!(!valid("infinity", Primitive::Double));
+        assert!(!valid("+infinity", Primitive::Double));
+        assert!(!valid("nan", Primitive::Double));
+        assert!(!valid("1e", Primitive::Double));
+        assert!(!valid("1e1.5", Primitive::Double));
+
+        assert!(valid("1", Primitive::Bool));
+        assert!(!valid("True", Primitive::Bool));
+    }
+
+    #[test]
+    fn report_problems() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="WebhookEvent">
+    <xs:sequence>
+      <xs:element minOccurs="0" maxOccurs="1" name="Data" />
+      <xs:element minOccurs="0" maxOccurs="1" name="Extra" type="xs:anyType" />
+      <xs:element minOccurs="1" maxOccurs="1" name="Created" type="xs:dateTime" />
+      <xs:group ref="Missing" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Loop">
+    <xs:complexContent mixed="false">
+      <xs:extension base="Loop" />
+    </xs:complexContent>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let (models, report) = ModelSchema::create_from_xml_with_report(xml_schema, false)
+            .expect("Creating ModelSchema");
+
+        assert_eq!(models.implementations.len(), 1);
+        assert_eq!(
+            report.diagnostics,
+            vec![
+                Diagnostic::warning("Unresolved group `Missing`").in_type("WebhookEvent"),
+                Diagnostic::error("Cyclic base type `Loop`").in_type("Loop"),
+            ]
+        );
+
+        // Elements without a type can have any content.
+        match &models.implementations[0] {
+            Implementation::Object(o) => assert_eq!(
+                o.fields,
+                vec![
+                    Field::new("Data", Primitive::AnyType).optional(),
+                    Field::new("Extra", Primitive::AnyType).optional(),
+                    Field::new("Created", Primitive::DateTime),
+                ]
+            ),
+            other => panic!("Expected WebhookEvent, got {:?}", other),
+        }
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let err = ModelSchema::create_from_xml_with_report(xml_schema, true)
+            .err()
+            .expect("Strict mode fails");
+        let report = err.downcast::<Report>().expect("Error is a Report");
+        assert!(report
+            .diagnostics
+            .iter()
+            .all(|d| d.severity == Severity::Error));
+    }
+
+    #[test]
+    fn translate_patterns() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="Code">
+    <xs:restriction base="xs:string">
+      <xs:pattern value="\i\c*" />
+      <xs:pattern value="[a-z-[aeiou]]+" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Latin">
+    <xs:restriction base="xs:string">
+      <xs:pattern value="[0-9]+" />
+      <xs:pattern value="\p{IsBasicLatin}+" />
+    </xs:restriction>
+  </xs:simpleType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let (models, report) = ModelSchema::create_from_xml_with_report(xml_schema, false)
+            .expect("Creating ModelSchema");
+
+        assert_eq!(
+            report.diagnostics,
+            vec![Diagnostic::warning(
+                "Dropping patterns, `\\p{IsBasicLatin}+` can't be checked: \
+                 Unicode block `IsBasicLatin` is not supported"
+            )
+            .in_type("Latin")]
+        );
+
+        let patterns = |name: &str| match models.implementations.iter().find(|i| i.name() == name) {
+            Some(Implementation::Newtype(n)) => n.facets.patterns.clone(),
+            other => panic!("Expected {}, got {:?}", name, other),
+        };
+        assert_eq!(
+            patterns("Code"),
+            vec![r"[_:\p{L}][\-._:\p{L}\p{Nd}\p{M}]*", "[a-z--[aeiou]]+"]
+        );
+        assert!(patterns("Latin").is_empty());
+        assert!(models.uses_regex());
+    }
+
+    #[test]
+    fn inline_groups() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:group name="Names">
+    <xs:sequence>
+      <xs:element name="FirstName" type="xs:string" />
+      <xs:element name="LastName" type="xs:string" />
+    </xs:sequence>
+  </xs:group>
+  <xs:group name="Loop">
+    <xs:sequence>
+      <xs:group ref="Loop" />
+    </xs:sequence>
+  </xs:group>
+  <xs:attributeGroup name="Versioned">
+    <xs:attribute name="Version" type="xs:int" use="required" />
+    <xs:attributeGroup ref="Tracked" />
+  </xs:attributeGroup>
+  <xs:attributeGroup name="Tracked">
+    <xs:attribute name="TraceId" type="xs:string" />
+  </xs:attributeGroup>
+  <xs:complexType name="Person">
+    <xs:sequence>
+      <xs:element name="Age" type="xs:int" />
+      <xs:group ref="tns:Names" minOccurs="0" />
+      <xs:group ref="Missing" />
+      <xs:group ref="Loop" />
+    </xs:sequence>
+    <xs:attributeGroup ref="Versioned" />
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let (models, report) = ModelSchema::create_from_xml_with_report(xml_schema, false)
+            .expect("Creating ModelSchema");
+
+        let person = match &models.implementations[..] {
+            [Implementation::Object(person)] => person,
+            other => panic!("Expected only Person, got {:?}", other),
+        };
+
+        assert_eq!(
+            person.fields,
+            vec![
+                Field::new("Age", Primitive::Int),
+                Field::new("FirstName", Primitive::String).optional(),
+                Field::new("LastName", Primitive::String).optional(),
+                Field::new("Version", Primitive::Int).attribute(),
+                Field::new("TraceId", Primitive::String)
+                    .attribute()
+                    .optional(),
+            ]
+        );
+        assert_eq!(
+            report.diagnostics,
+            vec![
+                Diagnostic::warning("Unresolved group `Missing`").in_type("Person"),
+                Diagnostic::error("Cyclic group `Loop`").in_type("Person"),
+            ]
+        );
+    }
+
+    #[test]
+    fn hoist_repeated_groups() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:group name="Line">
+    <xs:sequence>
+      <xs:element name="Article" type="xs:string" />
+      <xs:element name="Quantity" type="xs:int" />
+    </xs:sequence>
+  </xs:group>
+  <xs:group name="Contact">
+    <xs:choice>
+      <xs:element name="Email" type="xs:string" />
+      <xs:element name="Phone" type="xs:string" />
+    </xs:choice>
+  </xs:group>
+  <xs:complexType name="Order">
+    <xs:sequence>
+      <xs:element name="Id" type="xs:int" />
+      <xs:group ref="Line" maxOccurs="unbounded" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Customer">
+    <xs:group ref="Contact" minOccurs="0" maxOccurs="3" />
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let (models, report) = ModelSchema::create_from_xml_with_report(xml_schema, false)
+            .expect("Creating ModelSchema");
+
+        let object = |name: &str| match models.implementations.iter().find(|i| i.name() == name) {
+            Some(Implementation::Object(o)) => o.fields.clone(),
+            other => panic!("Expected {}, got {:?}", name, other),
+        };
+        assert_eq!(
+            object("Order"),
+            vec![
+                Field::new("Id", Primitive::Int),
+                Field::new("Line", TypeName::array(TypeName::object("OrderLine"))).bounded(1, None),
+            ]
+        );
+        assert_eq!(
+            object("Customer"),
+            vec![Field::new(
+                "Choice",
+                TypeName::array(TypeName::object("CustomerChoice"))
+            )
+            .choice()
+            .optional()
+            .bounded(0, Some(3)),]
+        );
+        assert_eq!(
+            object("OrderLine"),
+            vec![
+                Field::new("Article", Primitive::String),
+                Field::new("Quantity", Primitive::Int),
+            ]
+        );
+        match models
+            .implementations
+            .iter()
+            .find(|i| i.name() == "CustomerChoice")
+        {
+            Some(Implementation::Choice(c)) => assert_eq!(c.variants.len(), 2),
+            other => panic!("Expected CustomerChoice, got {:?}", other),
+        }
+        assert_eq!(
+            report.diagnostics,
+            vec![Diagnostic::warning(
+                "Each item of repeated group `Line` is wrapped in a `Line` element"
+            )
+            .in_type("Order")
+            .in_field("Line")]
+        );
+    }
+
+    #[test]
+    fn keep_sequence_order() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:group name="Names">
+    <xs:sequence>
+      <xs:element name="FirstName" type="xs:string" />
+      <xs:element name="LastName" type="xs:string" />
+    </xs:sequence>
+  </xs:group>
+  <xs:complexType name="Person">
+    <xs:sequence>
+      <xs:annotation>
+        <xs:documentation>A person.</xs:documentation>
+      </xs:annotation>
+      <xs:element name="Id" type="xs:int" />
+      <xs:group ref="Names" />
+      <xs:choice>
+        <xs:element name="Email" type="xs:string" />
+        <xs:element name="Phone" type="xs:string" />
+      </xs:choice>
+      <xs:sequence>
+        <xs:element name="Note" type="xs:string" />
+      </xs:sequence>
+      <xs:element name="Age" type="xs:int" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let models = ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+
+        let person = match &models.implementations[..] {
+            [Implementation::Object(person)] => person,
+            other => panic!("Expected only Person, got {:?}", other),
+        };
+
+        let names = person
+            .fields
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "Id",
+                "FirstName",
+                "LastName",
+                "Email",
+                "Phone",
+                "Note",
+                "Age"
+            ]
+        );
+    }
+
+    #[test]
+    fn flatten_choices_with_siblings() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Payment">
+    <xs:sequence>
+      <xs:element name="Amount" type="xs:double" />
+      <xs:choice>
+        <xs:element name="Iban" type="xs:string" />
+        <xs:element name="Bankgiro" type="xs:string" />
+      </xs:choice>
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Batch">
+    <xs:sequence>
+      <xs:element name="Note" type="xs:string" />
+      <xs:choice maxOccurs="3">
+        <xs:element name="Iban" type="xs:string" />
+        <xs:element name="Ocr" type="xs:string" maxOccurs="2" />
+      </xs:choice>
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Transfer">
+    <xs:choice>
+      <xs:element name="Iban" type="xs:string" />
+      <xs:element name="Bankgiro" type="xs:string" />
+    </xs:choice>
+    <xs:attribute name="Currency" type="xs:string" />
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let xml_schema: xml::Schema = xml::de(s.as_bytes()).expect("Parsing");
+        let (models, report) = ModelSchema::create_from_xml_with_report(xml_schema, false)
+            .expect("Creating ModelSchema");
+
+        let fields = |name: &str| match models.implementations.iter().find(|i| i.name() == name) {
+            Some(Implementation::Object(object)) => object.fields.clone(),
+            other => panic!("Expected object {}, got {:?}", name, other),
+        };
+
+        assert_eq!(
+            fields("Payment"),
+            vec![
+                Field::new("Amount", Primitive::Double),
+                Field::new("Iban", Primitive::String).optional(),
+                Field::new("Bankgiro", Primitive::String).optional(),
+            ]
+        );
+        assert_eq!(
+            fields("Batch"),
+            vec![
+                Field::new("Note", Primitive::String),
+                Field::new("Iban", TypeName::array(Primitive::String))
+                    .optional()
+                    .bounded(0, Some(3)),
+                Field::new("Ocr", TypeName::array(Primitive::String))
+                    .optional()
+                    .bounded(0, Some(6)),
+            ]
+        );
+        assert_eq!(
+            fields("Transfer"),
+            vec![
+                Field::new("Choice", TypeName::object("TransferChoice")).choice(),
+                Field::new("Currency", Primitive::String)
+                    .attribute()
+                    .optional(),
+            ]
+        );
+
+        let choices = models
+            .implementations
+            .iter()
+            .filter(|i| matches!(i, Implementation::Choice(_)))
+            .map(|i| i.name())
+            .collect::<Vec<_>>();
+        assert_eq!(choices, vec!["TransferChoice"]);
+
+        assert_eq!(
+            report.diagnostics,
+            vec![
+                Diagnostic::warning(
+                    "Choice with sibling elements is flattened into optional fields"
+                )
+                .in_type("Payment")
+                .in_field("Choice"),
+                Diagnostic::warning(
+                    "Repeated choice with sibling elements is flattened into array fields, \
+                     the order of its alternatives is lost"
+                )
+                .in_type("Batch")
+                .in_field("Choice"),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_implementations() {
+        let models = || {
+            let xml_schema: xml::Schema =
+                xml::de(BILLECTA_XSD.as_bytes()).expect("Parsing Billecta XSD");
+            ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema")
+        };
+        let names = |models: &ModelSchema| {
+            models
+                .implementations
+                .iter()
+                .map(|i| i.name().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut included = models();
+        included
+            .filter(&["AccountingExportCreation"], &[])
+            .expect("Filtering");
+        assert_eq!(
+            names(&included),
+            vec![
+                "AccountingExportCreation",
+                "AccountingExportDateSelectionType",
+                "AccountingExportFormatType",
+                "AccountingRecordType",
+            ]
+        );
+
+        included
+            .filter(&[], &["AccountingExportCreation"])
+            .expect("Excluding a type nothing depends on");
+        assert_eq!(
+            names(&included),
+            vec![
+                "AccountingExportDateSelectionType",
+                "AccountingExportFormatType",
+                "AccountingRecordType",
+            ]
+        );
+
+        // Kept types can't refer to excluded ones.
+        let mut excluded = models();
+        let err = excluded
+            .filter(
+                &["AccountingExportCreation"],
+                &["AccountingExportFormatType"],
+            )
+            .expect_err("Excluding a dependency");
+        assert_eq!(
+            err.to_string(),
+            "Cannot exclude `AccountingExportFormatType`, `AccountingExportCreation` depends on it"
+        );
+    }
 }
@@ -0,0 +1,127 @@
+//! Translation of XSD regular expressions to the syntax of the `regex` crate.
+
+/// XSD's `\i`, the first character of an XML name.
+const NAME_START: &str = r"[_:\p{L}]";
+/// XSD's `\c`, any character of an XML name.
+const NAME_CHAR: &str = r"[\-._:\p{L}\p{Nd}\p{M}]";
+
+/// Translates an XSD pattern to a `regex` pattern, to be anchored at both
+/// ends by the caller since XSD patterns always match the whole value.
+///
+/// Multi-character escapes are written as classes, which the `regex` crate
+/// also accepts nested in other classes, and class subtractions such as
+/// `[a-z-[aeiou]]` as class differences. Unicode block escapes such as
+/// `\p{IsBasicLatin}` have no counterpart and are an error.
+pub fn translate(pattern: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    let mut depth = 0;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped = chars
+                    .next()
+                    .ok_or_else(|| "the pattern ends with `\\`".to_string())?;
+                match escaped {
+                    'i' => out.push_str(NAME_START),
+                    'I' => out.push_str(&negate(NAME_START)),
+                    'c' => out.push_str(NAME_CHAR),
+                    'C' => out.push_str(&negate(NAME_CHAR)),
+                    's' => out.push_str(r"[ \t\n\r]"),
+                    'S' => out.push_str(r"[^ \t\n\r]"),
+                    'w' => out.push_str(r"[^\p{P}\p{Z}\p{C}]"),
+                    'W' => out.push_str(r"[\p{P}\p{Z}\p{C}]"),
+                    'p' | 'P' => {
+                        let mut property = String::new();
+                        if chars.next() != Some('{') {
+                            return Err(format!("`\\{}` must be followed by `{{`", escaped));
+                        }
+                        loop {
+                            match chars.next() {
+                                Some('}') => break,
+                                Some(c) => property.push(c),
+                                None => return Err(format!("`\\{}` is not closed", escaped)),
+                            }
+                        }
+                        if property.starts_with("Is") {
+                            return Err(format!("Unicode block `{}` is not supported", property));
+                        }
+                        out.push_str(&format!("\\{}{{{}}}", escaped, property));
+                    }
+                    other => {
+                        out.push('\\');
+                        out.push(other);
+                    }
+                }
+            }
+            '[' => {
+                depth += 1;
+                out.push('[');
+                if chars.peek() == Some(&'^') {
+                    chars.next();
+                    out.push('^');
+                }
+            }
+            ']' if depth > 0 => {
+                depth -= 1;
+                out.push(']');
+            }
+            '-' if depth > 0 && chars.peek() == Some(&'[') => out.push_str("--"),
+            '&' | '~' if depth > 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            '^' | '$' if depth == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            '.' if depth == 0 => out.push_str(r"[^\n\r]"),
+            other => out.push(other),
+        }
+    }
+
+    regex_syntax::Parser::new()
+        .parse(&out)
+        .map_err(|_| format!("`{}` is not a valid regular expression", pattern))?;
+
+    Ok(out)
+}
+
+fn negate(class: &str) -> String {
+    format!("[^{}", &class[1..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translate_patterns() {
+        assert_eq!(Ok("[0-9]+".into()), translate("[0-9]+"));
+        assert_eq!(Ok(r"\d{3}-\d{2}".into()), translate(r"\d{3}-\d{2}"));
+        assert_eq!(
+            Ok(r"[_:\p{L}][\-._:\p{L}\p{Nd}\p{M}]*".into()),
+            translate(r"\i\c*")
+        );
+        assert_eq!(Ok(r"[^\-._:\p{L}\p{Nd}\p{M}]".into()), translate(r"\C"));
+        assert_eq!(Ok("[a-z--[aeiou]]+".into()), translate("[a-z-[aeiou]]+"));
+        assert_eq!(Ok(r"\^[^\n\r]\$".into()), translate("^.$"));
+        assert_eq!(Ok(r"[.^$\&\~]".into()), translate("[.^$&~]"));
+        assert_eq!(Ok(r"[^a-z]".into()), translate("[^a-z]"));
+        assert_eq!(Ok(r"\p{Lu}\P{Nd}".into()), translate(r"\p{Lu}\P{Nd}"));
+    }
+
+    #[test]
+    fn untranslatable_patterns() {
+        assert_eq!(
+            Err("Unicode block `IsBasicLatin` is not supported".into()),
+            translate(r"\p{IsBasicLatin}+")
+        );
+        assert_eq!(
+            Err("`[0-9` is not a valid regular expression".into()),
+            translate("[0-9")
+        );
+        assert_eq!(Err("the pattern ends with `\\`".into()), translate("a\\"));
+    }
+}
@@ -1,58 +1,307 @@
-use crate::{CodeFormatter, ObjectImpl, Primitive, TypeName};
-use heck::SnakeCase;
-use std::{collections::HashSet, fmt};
+use crate::{
+    AbstractImpl, ChoiceImpl, CodeFormatter, EnumImpl, Field, FieldKind, Implementation, ListImpl,
+    NewtypeImpl, ObjectImpl, Primitive, TypeName, UnionImpl, WhiteSpace,
+};
+use heck::{CamelCase, SnakeCase};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+};
 
 static INDENT: &str = "    ";
 
-/// Formatter that writes ObjectImpls to Rust files.
-pub struct RustFmt;
+/// The module writing documents, added to the output with serde.
+static XML_WRITER: &str = include_str!("rust_fmt/xml_writer.rs");
 
-impl CodeFormatter for RustFmt {
-    fn write_impl_file<W>(&mut self, w: &mut W, object: &ObjectImpl) -> fmt::Result
+/// Largest exact number of items written as an array instead of a Vec.
+/// Serde implements its traits for arrays up to this size.
+static MAX_FIXED_ARRAY: u64 = 32;
+
+/// Words that cannot be used as plain identifiers in Rust 2018.
+static KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "crate",
+    "do", "dyn", "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in",
+    "let", "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref",
+    "return", "self", "Self", "static", "struct", "super", "trait", "true", "try", "type",
+    "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Formatter that writes ObjectImpls and EnumImpls to Rust files.
+#[derive(Debug, Clone, Default)]
+pub struct RustFmt {
+    serde: bool,
+    types: HashMap<Primitive, TypeMapping>,
+    nillable: Option<NillableStyle>,
+}
+
+/// How fields that may be nil are written, see `RustFmt::nillable`.
+/// Arrays are written as is, nil or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NillableStyle {
+    /// `Option<T>`, not telling a nil element from a missing one.
+    Option,
+    /// `Option<Option<T>>` for optional fields, where `Some(None)` is nil.
+    /// Required fields are `Option<T>`.
+    NestedOption,
+    /// A generic type at the given path, such as `crate::Nillable`
+    /// written as `crate::Nillable<T>`. With serde, the type must convert
+    /// from `Option<T>` and by reference to `Option<&T>`, `None` being nil.
+    Wrapper(String),
+}
+
+/// A Rust type written in place of a Primitive, such as
+/// `chrono::DateTime<Utc>` for `xs:dateTime`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeMapping {
+    rust_type: String,
+    imports: Vec<String>,
+    serde_with: Option<String>,
+}
+
+impl TypeMapping {
+    /// The type as written in fields, such as `DateTime<Utc>`.
+    pub fn new(rust_type: impl Into<String>) -> Self {
+        Self {
+            rust_type: rust_type.into(),
+            imports: Vec::new(),
+            serde_with: None,
+        }
+    }
+
+    /// Imports `path` into every file using the type,
+    /// such as `chrono::{DateTime, Utc}`.
+    pub fn import(mut self, path: impl Into<String>) -> Self {
+        self.imports.push(path.into());
+        self
+    }
+
+    /// Serializes the type through `#[serde(with = "module")]`, for types
+    /// not serialized as wanted by themselves, such as base64 in a `Vec<u8>`.
+    /// Optional fields use `module::option` instead.
+    /// Arrays of the type, fields that may be nil and elements with
+    /// a default or fixed value are serialized without the module.
+    pub fn serde_with(mut self, module: impl Into<String>) -> Self {
+        self.serde_with = Some(module.into());
+        self
+    }
+}
+
+impl RustFmt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derive `Serialize` and `Deserialize` for all generated types,
+    /// renaming fields and variants to the names used in the schema.
+    /// XML attributes are serialized with a leading `@` in their name,
+    /// and deserialized by their plain name as serde-xml-rs reads them.
+    ///
+    /// Types of top level elements get functions reading documents with
+    /// `serde-xml-rs` and writing them with an `xml_writer` module added
+    /// to the output.
+    pub fn with_serde(mut self) -> Self {
+        self.serde = true;
+        self
+    }
+
+    /// Writes `primitive` as the type of `mapping` instead of the default.
+    pub fn map_type(mut self, primitive: Primitive, mapping: TypeMapping) -> Self {
+        self.types.insert(primitive, mapping);
+        self
+    }
+
+    /// Writes fields that may be nil as `style`, which are otherwise
+    /// written as if they may not be. With serde, they are read and
+    /// written as elements with `xsi:nil` through the `xml_writer` module.
+    pub fn nillable(mut self, style: NillableStyle) -> Self {
+        self.nillable = Some(style);
+        self
+    }
+
+    /// The `serde(with)` module of a field, if its type has one.
+    fn serde_with(&self, f: &Field) -> Option<String> {
+        let module = match &f.type_name {
+            TypeName::Primitive(p) => self.types.get(p)?.serde_with.as_ref()?,
+            _ => return None,
+        };
+
+        match (self.wrapper(f), self.options(f)) {
+            (None, 0) => Some(module.clone()),
+            (None, 1) => Some(format!("{}::option", module)),
+            _ => None,
+        }
+    }
+
+    /// The type wrapping the value of a field that may be nil, if any.
+    fn wrapper(&self, f: &Field) -> Option<&str> {
+        match &self.nillable {
+            Some(NillableStyle::Wrapper(path)) if is_nillable(f) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The number of `Option`s the value of a field is wrapped in.
+    /// Fields with a default or fixed value are not wrapped.
+    fn options(&self, f: &Field) -> usize {
+        let nil = is_nillable(f)
+            && match self.nillable {
+                Some(NillableStyle::Option) => f.required,
+                Some(NillableStyle::NestedOption) => true,
+                Some(NillableStyle::Wrapper(_)) | None => false,
+            };
+
+        usize::from(is_optional(f)) + usize::from(nil)
+    }
+
+    /// The `serde` attribute reading and writing a field that may be nil
+    /// through the `nil` module of `xml_writer`.
+    fn nil_attribute(&mut self, f: &Field) -> Result<Option<String>, fmt::Error> {
+        let style = match &self.nillable {
+            Some(style) if is_nillable(f) => style.clone(),
+            _ => return Ok(None),
+        };
+
+        let option = if is_optional(f) { "::option" } else { "" };
+        let attribute = match style {
+            NillableStyle::Option => String::from("with = \"xml_writer::nil\""),
+            NillableStyle::NestedOption => format!("with = \"xml_writer::nil{}\"", option),
+            NillableStyle::Wrapper(_) => {
+                let mut value = String::new();
+                self.write_value_type(&mut value, f)?;
+                format!(
+                    "serialize_with = \"xml_writer::nil::wrapped{0}::serialize::<{1}, _, _>\", \
+                     deserialize_with = \"xml_writer::nil::wrapped{0}::deserialize::<{1}, _, _>\"",
+                    option, value
+                )
+            }
+        };
+
+        Ok(Some(attribute))
+    }
+
+    /// The module reading and writing the fields that may be nil
+    /// or elements with a value, if any.
+    fn reader_import(&self, fields: &[Field]) -> Option<&'static str> {
+        let nil = self.nillable.is_some() && fields.iter().any(is_nillable);
+        let empty = fields.iter().any(|f| empty_value(f).is_some());
+        if self.serde && (nil || empty) {
+            Some("xml_writer")
+        } else {
+            None
+        }
+    }
+
+    /// The imports of the mapped types used by `types`.
+    fn type_imports<'a, I>(&'a self, types: I) -> BTreeSet<&'a str>
+    where
+        I: IntoIterator<Item = &'a TypeName>,
+    {
+        types
+            .into_iter()
+            .filter_map(TypeName::primitive)
+            .filter_map(|p| self.types.get(p))
+            .flat_map(|m| m.imports.iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Writes a `use` for each import of the mapped types used by `types`.
+    fn write_type_imports<'a, W, I>(&'a self, w: &mut W, types: I) -> fmt::Result
     where
         W: fmt::Write,
+        I: IntoIterator<Item = &'a TypeName>,
     {
-        if object
-            .fields
-            .iter()
-            .any(|f| f.type_name.import_statement().is_some())
-        {
-            let mut imports = object
-                .fields
-                .iter()
-                .filter_map(|f| f.type_name.import_statement())
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .collect::<Vec<_>>();
+        let imports = self.type_imports(types);
+        for import in &imports {
+            writeln!(w, "use {};", import)?;
+        }
+
+        if imports.is_empty() {
+            Ok(())
+        } else {
+            w.write_char('\n')
+        }
+    }
+
+    /// Writes the struct of an ObjectImpl, without any imports.
+    fn write_object<W>(&mut self, w: &mut W, object: &ObjectImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        // Renaming each field is only needed if serde can't derive the
+        // names from the field names.
+        let rename_all = object.fields.iter().all(|f| {
+            f.kind == FieldKind::Element
+                && pascal_case(field_ident(&f.name).trim_start_matches("r#")) == f.name
+        });
 
-            imports.sort_unstable();
+        // Serde reads the elements of a struct in any order regardless,
+        // so the ordering is only told to the reader.
+        if object.unordered {
+            w.write_str("/// The elements may appear in any order.\n")?;
+        }
 
-            w.write_str("use super::{\n")?;
-            for s in imports {
-                w.write_str(INDENT)?;
-                w.write_str(&s)?;
-                w.write_str(",\n")?;
+        if self.serde {
+            w.write_str("#[derive(Serialize, Deserialize)]\n")?;
+            if rename_all && !object.fields.is_empty() {
+                w.write_str("#[serde(rename_all = \"PascalCase\")]\n")?;
             }
-
-            w.write_str("};\n\n")?;
         }
 
+        write_allow_case(w, [&object.name])?;
         writeln!(w, "pub struct {} {{", &object.name)?;
         for f in &object.fields {
+            if self.serde {
+                let mut attributes = Vec::new();
+                match f.kind {
+                    FieldKind::Attribute => attributes.push(format!(
+                        "rename(serialize = \"@{}\", deserialize = {:?})",
+                        f.name, f.name
+                    )),
+                    FieldKind::Element if !rename_all => {
+                        attributes.push(format!("rename = {:?}", f.name))
+                    }
+                    FieldKind::Element => (),
+                    FieldKind::Choice => attributes.push(String::from("rename = \"$value\"")),
+                }
+                let with = match f.item_name {
+                    Some(_) => Some(format!("with = \"{}\"", items_module(&object.name, f))),
+                    None if empty_value(f).is_some() => Some(format!(
+                        "deserialize_with = \"{}::{}\"",
+                        object.name,
+                        deserialize_fn(f)
+                    )),
+                    None => match self.nil_attribute(f)? {
+                        Some(nil) => Some(nil),
+                        None => self.serde_with(f).map(|m| format!("with = \"{}\"", m)),
+                    },
+                };
+                if let Some(with) = with {
+                    attributes.push(with);
+                    if is_optional(f) {
+                        attributes.push(String::from("default"));
+                    }
+                }
+                if field_value(f).is_some() && f.kind == FieldKind::Attribute {
+                    attributes.push(format!("default = \"{}::{}\"", object.name, default_fn(f)));
+                }
+                if is_optional(f) {
+                    attributes.push(String::from("skip_serializing_if = \"Option::is_none\""));
+                } else if !f.required && is_repeated(f) {
+                    attributes.push(String::from("default"));
+                }
+
+                if !attributes.is_empty() {
+                    w.write_str(INDENT)?;
+                    writeln!(w, "#[serde({})]", attributes.join(", "))?;
+                }
+            }
+
             w.write_str(INDENT)?;
 
             w.write_str("pub ")?;
-            w.write_str(&f.name.to_snake_case())?;
+            w.write_str(&field_ident(&f.name))?;
             w.write_str(": ")?;
-
-            if f.required {
-                self.write_type(w, &f.type_name)?;
-            } else {
-                w.write_str("Option<")?;
-                self.write_type(w, &f.type_name)?;
-                w.write_char('>')?;
-            }
-
+            self.write_field_type(w, f)?;
             w.write_char(',')?;
             w.write_char('\n')?;
         }
@@ -69,12 +318,12 @@ impl CodeFormatter for RustFmt {
         w.write_str("pub fn required(")?;
         w.write_char('\n')?;
 
-        for rf in object.fields.iter().filter(|f| f.required) {
+        for rf in object.fields.iter().filter(|f| is_parameter(f)) {
             w.write_str(INDENT)?;
             w.write_str(INDENT)?;
-            w.write_str(&rf.name.to_snake_case())?;
+            w.write_str(&field_ident(&rf.name))?;
             w.write_str(": ")?;
-            self.write_type(w, &rf.type_name)?;
+            self.write_field_type(w, rf)?;
             w.write_str(",\n")?;
         }
         w.write_str(INDENT)?;
@@ -84,21 +333,19 @@ impl CodeFormatter for RustFmt {
         w.write_str(INDENT)?;
         w.write_str("Self {\n")?;
 
-        for rf in object.fields.iter().filter(|f| f.required) {
+        for rf in object.fields.iter().filter(|f| is_parameter(f)) {
             w.write_str(INDENT)?;
             w.write_str(INDENT)?;
             w.write_str(INDENT)?;
-            w.write_str(&rf.name.to_snake_case())?;
+            w.write_str(&field_ident(&rf.name))?;
             w.write_str(",\n")?;
         }
 
-        for rf in object.fields.iter().filter(|f| !f.required) {
+        for rf in object.fields.iter().filter(|f| !is_parameter(f)) {
             w.write_str(INDENT)?;
             w.write_str(INDENT)?;
             w.write_str(INDENT)?;
-            w.write_str(&rf.name.to_snake_case())?;
-            w.write_str(": None")?;
-            w.write_str(",\n")?;
+            write_field_init(w, rf)?;
         }
 
         w.write_str(INDENT)?;
@@ -107,53 +354,3753 @@ impl CodeFormatter for RustFmt {
 
         w.write_str(INDENT)?;
         w.write_str("}\n")?;
+
+        self.write_default_fns(w, object)?;
+        self.write_validate(w, object)?;
+        if self.serde {
+            self.write_root_helpers(w, &object.roots)?;
+        }
+
         w.write_char('}')?;
 
+        // Objects whose required fields all have a value can be defaulted.
+        let fields = &object.fields;
+        if fields.iter().any(|f| field_value(f).is_some())
+            && fields
+                .iter()
+                .all(|f| !f.required || field_value(f).is_some())
+        {
+            writeln!(w, "\n\nimpl Default for {} {{", object.name)?;
+            write_line(w, 1, "fn default() -> Self {")?;
+            write_line(w, 2, "Self {")?;
+            for f in fields {
+                w.write_str(&INDENT.repeat(3))?;
+                write_field_init(w, f)?;
+            }
+            write_line(w, 2, "}")?;
+            write_line(w, 1, "}")?;
+            w.write_char('}')?;
+        }
+
+        if self.serde {
+            for f in object.fields.iter().filter(|f| f.item_name.is_some()) {
+                w.write_str("\n\n")?;
+                self.write_items_module(w, &object.name, f)?;
+            }
+        }
+
         Ok(())
     }
 
-    fn write_type<W>(&mut self, w: &mut W, p: &TypeName) -> fmt::Result
+    /// Writes a function returning the value of each field with a default
+    /// or fixed value, for the constructors and `#[serde(default)]` of
+    /// attributes. With serde, elements also get a function reading them,
+    /// as their value is that of an empty element rather than a missing one.
+    fn write_default_fns<W>(&mut self, w: &mut W, object: &ObjectImpl) -> fmt::Result
     where
         W: fmt::Write,
     {
-        match p {
-            TypeName::Primitive(Primitive::Bool) => w.write_str("bool"),
-            TypeName::Primitive(Primitive::Int) => w.write_str("i32"),
-            TypeName::Primitive(Primitive::Long) => w.write_str("i64"),
-            TypeName::Primitive(Primitive::Double) => w.write_str("f64"),
-            TypeName::Primitive(Primitive::String) => w.write_str("String"),
-            // TypeName::Nullable(ref inner) => {
-            //     w.write_str("Option<")?;
-            //     self.write_type(w, inner)?;
-            //     w.write_str(">")
-            // }
-            TypeName::Array(ref inner) => {
-                w.write_str("Vec<")?;
+        for f in &object.fields {
+            let value = match field_value(f) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let mut value_type = String::new();
+            self.write_value_type(&mut value_type, f)?;
+
+            w.write_char('\n')?;
+            write_line(
+                w,
+                1,
+                &format!("pub fn {}() -> {} {{", default_fn(f), value_type),
+            )?;
+            write_line(w, 2, &self.value_expr(f, value))?;
+            write_line(w, 1, "}")?;
+
+            if !self.serde || empty_value(f).is_none() {
+                continue;
+            }
+
+            let mut field_type = String::new();
+            self.write_field_type(&mut field_type, f)?;
+            let some = if is_optional(f) { ".map(Some)" } else { "" };
+
+            w.write_char('\n')?;
+            write_line(
+                w,
+                1,
+                &format!(
+                    "fn {}<'de, D>(deserializer: D) -> Result<{}, D::Error>",
+                    deserialize_fn(f),
+                    field_type
+                ),
+            )?;
+            write_line(w, 1, "where")?;
+            write_line(w, 2, "D: serde::Deserializer<'de>,")?;
+            write_line(w, 1, "{")?;
+            write_line(
+                w,
+                2,
+                &format!(
+                    "xml_writer::text_or_else(deserializer, Self::{}){}",
+                    default_fn(f),
+                    some
+                ),
+            )?;
+            write_line(w, 1, "}")?;
+        }
+
+        Ok(())
+    }
+
+    /// A Rust expression of `value` for the type of `f`. Types that aren't
+    /// written as literals are parsed with their `FromStr`.
+    fn value_expr(&self, f: &Field, value: &str) -> String {
+        let rust_type = match &f.type_name {
+            TypeName::Primitive(p) if !self.types.contains_key(p) => primitive_type(p),
+            _ => "",
+        };
+
+        let trimmed = value.trim().trim_start_matches('+');
+        match rust_type {
+            "String" => format!("String::from({:?})", value),
+            "bool" => String::from(if trimmed == "true" || trimmed == "1" {
+                "true"
+            } else {
+                "false"
+            }),
+            "f32" | "f64" => match trimmed.parse::<f64>() {
+                Ok(n) if n.is_nan() => format!("{}::NAN", rust_type),
+                Ok(n) if n == f64::INFINITY => format!("{}::INFINITY", rust_type),
+                Ok(n) if n == f64::NEG_INFINITY => format!("{}::NEG_INFINITY", rust_type),
+                Ok(n) => format!("{:?}", n),
+                Err(_) => format!("{:?}.parse().expect(\"valid value\")", value),
+            },
+            "" => format!("{:?}.parse().expect(\"valid value\")", value),
+            _ => trimmed.to_string(),
+        }
+    }
+
+    /// Writes functions reading documents with any of `roots` as their
+    /// root element, and writing them with each of the roots.
+    fn write_root_helpers<W>(&mut self, w: &mut W, roots: &[String]) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let (first, others) = match roots.split_first() {
+            Some(split) => split,
+            None => return Ok(()),
+        };
+
+        let names = roots
+            .iter()
+            .map(|root| format!("`{}`", root))
+            .collect::<Vec<_>>();
+        let names = match names.split_last() {
+            Some((last, init)) if !init.is_empty() => format!("{} or {}", init.join(", "), last),
+            _ => names.join(""),
+        };
+
+        w.write_char('\n')?;
+        write_line(
+            w,
+            1,
+            &format!("/// Reads a document whose root element is {}.", names),
+        )?;
+        write_line(
+            w,
+            1,
+            "pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {",
+        )?;
+        write_line(w, 2, "serde_xml_rs::from_str(s)")?;
+        write_line(w, 1, "}")?;
+
+        // The first root gets the plain name, as most types have only one.
+        let functions = std::iter::once((String::from("to_xml_string"), first)).chain(
+            others
+                .iter()
+                .map(|root| (format!("to_{}_xml_string", root.to_snake_case()), root)),
+        );
+        for (function, root) in functions {
+            w.write_char('\n')?;
+            write_line(
+                w,
+                1,
+                &format!("/// Writes a document whose root element is `{}`.", root),
+            )?;
+            write_line(
+                w,
+                1,
+                &format!(
+                    "pub fn {}(&self) -> Result<String, xml_writer::Error> {{",
+                    function
+                ),
+            )?;
+            write_line(w, 2, &format!("xml_writer::to_string(self, {:?})", root))?;
+            write_line(w, 1, "}")?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes an `impl` block with the functions of `write_root_helpers`,
+    /// for types whose other functions don't need one.
+    fn write_root_impl<W>(&mut self, w: &mut W, name: &str, roots: &[String]) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if !self.serde || roots.is_empty() {
+            return Ok(());
+        }
+
+        write!(w, "\n\nimpl {} {{", name)?;
+        self.write_root_helpers(w, roots)?;
+        w.write_char('}')
+    }
+
+    /// Writes the module writing documents, if the types derive serde.
+    fn write_xml_writer<W>(&mut self, w: &mut W) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if !self.serde {
+            return Ok(());
+        }
+
+        // The tests of the module are left out.
+        let module = XML_WRITER
+            .split("\n#[cfg(test)]\n")
+            .next()
+            .unwrap_or_default();
+
+        w.write_str("\n#[allow(dead_code)]\nmod xml_writer {\n")?;
+        for line in module.lines() {
+            write_line(w, 1, line)?;
+        }
+        w.write_str("}\n")
+    }
+
+    /// Declares and exports the module of each implementation.
+    fn write_declarations<W>(
+        &mut self,
+        w: &mut W,
+        implementations: &[Implementation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        for imp in implementations {
+            writeln!(w, "mod {};", module_ident(imp))?;
+        }
+
+        if !implementations.is_empty() {
+            w.write_char('\n')?;
+        }
+
+        for imp in implementations {
+            writeln!(w, "pub use {}::{};", module_ident(imp), imp.name())?;
+        }
+
+        Ok(())
+    }
+
+    /// The module of the functions of `write_root_helpers`,
+    /// for the files of types with `roots`.
+    fn root_imports(&self, roots: &[String]) -> Option<&'static str> {
+        if self.serde && !roots.is_empty() {
+            Some("xml_writer")
+        } else {
+            None
+        }
+    }
+
+    /// Writes the type of a field, wrapped in an `Option` if not required
+    /// and as chosen by `RustFmt::nillable` if it may be nil.
+    fn write_field_type<W>(&mut self, w: &mut W, f: &Field) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let options = self.options(f);
+        let wrapper = self.wrapper(f).map(String::from);
+
+        for _ in 0..options {
+            w.write_str("Option<")?;
+        }
+        if let Some(wrapper) = &wrapper {
+            write!(w, "{}<", wrapper)?;
+        }
+
+        self.write_value_type(w, f)?;
+
+        if wrapper.is_some() {
+            w.write_char('>')?;
+        }
+        for _ in 0..options {
+            w.write_char('>')?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the type of a field's value. Arrays with a small exact
+    /// number of items are written as fixed size arrays.
+    fn write_value_type<W>(&mut self, w: &mut W, f: &Field) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match (&f.type_name, fixed_size(f)) {
+            (TypeName::Array(inner), Some(n)) => {
+                w.write_char('[')?;
                 self.write_type(w, inner)?;
-                w.write_str(">")
+                write!(w, "; {}]", n)
             }
-            TypeName::Object(ref name) => w.write_str(&name),
+            (type_name, _) => self.write_type(w, type_name),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{Field, Primitive};
+    /// Writes a `validate` method checking the number of items in bounded
+    /// arrays, if there are any bounds that the types do not enforce.
+    fn write_validate<W>(&mut self, w: &mut W, object: &ObjectImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let bounded = object
+            .fields
+            .iter()
+            .filter(|f| fixed_size(f).is_none())
+            .filter_map(|f| f.bounds.map(|b| (f, b)))
+            .filter(|(_, b)| b.min > 0 || b.max.is_some())
+            .collect::<Vec<_>>();
+
+        if bounded.is_empty() {
+            return Ok(());
+        }
+
+        w.write_char('\n')?;
+        write_line(w, 1, "/// Checks the number of items in bounded arrays.")?;
+        write_line(w, 1, "pub fn validate(&self) -> Result<(), String> {")?;
+
+        for (f, bounds) in bounded {
+            let (range, message) = match bounds.max {
+                Some(max) => (
+                    format!("{}..={}", bounds.min, max),
+                    format!("between {} and {}", bounds.min, max),
+                ),
+                None => (
+                    format!("{}..", bounds.min),
+                    format!("at least {}", bounds.min),
+                ),
+            };
+            let error = format!(
+                "return Err(String::from(\"`{}` must have {} items\"));",
+                f.name, message
+            );
+
+            if !is_optional(f) {
+                write_line(
+                    w,
+                    2,
+                    &format!(
+                        "if !({}).contains(&self.{}.len()) {{",
+                        range,
+                        field_ident(&f.name)
+                    ),
+                )?;
+                write_line(w, 3, &error)?;
+                write_line(w, 2, "}")?;
+            } else {
+                write_line(
+                    w,
+                    2,
+                    &format!("if let Some(items) = &self.{} {{", field_ident(&f.name)),
+                )?;
+                write_line(w, 3, &format!("if !({}).contains(&items.len()) {{", range))?;
+                write_line(w, 4, &error)?;
+                write_line(w, 3, "}")?;
+                write_line(w, 2, "}")?;
+            }
+        }
+
+        write_line(w, 2, "Ok(())")?;
+        write_line(w, 1, "}")
+    }
+
+    /// Writes a module for `#[serde(with = ..)]` that nests every item
+    /// of an array field in an element named after `Field::item_name`.
+    fn write_items_module<W>(&mut self, w: &mut W, owner: &str, f: &Field) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let item_name = f.item_name.as_deref().unwrap_or_default();
+        let mut item_type = String::new();
+        match f.type_name {
+            TypeName::Array(ref inner) => self.write_type(&mut item_type, inner)?,
+            ref other => self.write_type(&mut item_type, other)?,
+        }
+        let mut field_type = String::new();
+        self.write_field_type(&mut field_type, f)?;
+
+        writeln!(w, "mod {} {{", items_module(owner, f))?;
+        for line in &[
+            "use super::*;",
+            "use serde::{Deserializer, Serializer};",
+            "",
+            "#[derive(Serialize)]",
+            "struct Items<'a> {",
+        ] {
+            write_line(w, 1, line)?;
+        }
+        write_line(w, 2, &format!("#[serde(rename = {:?})]", item_name))?;
+        write_line(w, 2, &format!("items: &'a [{}],", item_type))?;
+        write_line(w, 1, "}")?;
+        write_line(w, 0, "")?;
+        write_line(w, 1, "#[derive(Deserialize)]")?;
+        write_line(w, 1, "struct OwnedItems {")?;
+        write_line(
+            w,
+            2,
+            &format!("#[serde(rename = {:?}, default)]", item_name),
+        )?;
+        write_line(w, 2, &format!("items: Vec<{}>,", item_type))?;
+        write_line(w, 1, "}")?;
+        write_line(w, 0, "")?;
+
+        write_line(
+            w,
+            1,
+            &format!(
+                "pub fn serialize<S>(items: &{}, serializer: S) -> Result<S::Ok, S::Error>",
+                field_type
+            ),
+        )?;
+        write_line(w, 1, "where")?;
+        write_line(w, 2, "S: Serializer,")?;
+        write_line(w, 1, "{")?;
+        if f.required {
+            write_line(w, 2, "Items { items }.serialize(serializer)")?;
+        } else {
+            write_line(w, 2, "match items {")?;
+            write_line(
+                w,
+                3,
+                "Some(items) => serializer.serialize_some(&Items { items }),",
+            )?;
+            write_line(w, 3, "None => serializer.serialize_none(),")?;
+            write_line(w, 2, "}")?;
+        }
+        write_line(w, 1, "}")?;
+        write_line(w, 0, "")?;
+
+        write_line(
+            w,
+            1,
+            &format!(
+                "pub fn deserialize<'de, D>(deserializer: D) -> Result<{}, D::Error>",
+                field_type
+            ),
+        )?;
+        write_line(w, 1, "where")?;
+        write_line(w, 2, "D: Deserializer<'de>,")?;
+        write_line(w, 1, "{")?;
+        if f.required {
+            write_line(
+                w,
+                2,
+                "OwnedItems::deserialize(deserializer).map(|i| i.items)",
+            )?;
+        } else {
+            write_line(
+                w,
+                2,
+                "Option::<OwnedItems>::deserialize(deserializer).map(|i| i.map(|i| i.items))",
+            )?;
+        }
+        write_line(w, 1, "}")?;
+        w.write_char('}')
+    }
+
+    /// Writes the enum of an AbstractImpl, without any imports.
+    /// With serde, the variant is picked by the `xsi:type` attribute.
+    fn write_abstract<W>(&mut self, w: &mut W, abstract_impl: &AbstractImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let name = &abstract_impl.name;
+
+        write_allow_case(w, Some(name).into_iter().chain(&abstract_impl.subtypes))?;
+        writeln!(w, "pub enum {} {{", name)?;
+        for subtype in &abstract_impl.subtypes {
+            w.write_str(INDENT)?;
+            writeln!(w, "{}({}),", subtype, subtype)?;
+        }
+        w.write_char('}')?;
+
+        if self.serde {
+            w.write_str("\n\n")?;
+            write_abstract_serde(w, abstract_impl)?;
+        }
+
+        self.write_root_impl(w, name, &abstract_impl.roots)
+    }
+
+    /// Writes the enum of a ChoiceImpl, without any imports.
+    /// Each variant holds the value of its alternative.
+    fn write_choice<W>(&mut self, w: &mut W, choice: &ChoiceImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let names = choice
+            .variants
+            .iter()
+            .map(|f| f.name.clone())
+            .collect::<Vec<_>>();
+        let idents = variant_idents(&names);
+
+        if self.serde {
+            w.write_str("#[derive(Serialize, Deserialize)]\n")?;
+        }
+
+        write_allow_case(w, [&choice.name])?;
+        writeln!(w, "pub enum {} {{", &choice.name)?;
+        for (ident, f) in idents.iter().zip(&choice.variants) {
+            if self.serde {
+                let mut attributes = Vec::new();
+                if ident != &f.name {
+                    attributes.push(format!("rename = {:?}", f.name));
+                }
+                let with = match f.item_name {
+                    Some(_) => Some(items_module(&choice.name, f)),
+                    None => self.serde_with(f),
+                };
+                if let Some(with) = with {
+                    attributes.push(format!("with = \"{}\"", with));
+                }
+
+                if !attributes.is_empty() {
+                    w.write_str(INDENT)?;
+                    writeln!(w, "#[serde({})]", attributes.join(", "))?;
+                }
+            }
+
+            w.write_str(INDENT)?;
+            w.write_str(ident)?;
+            w.write_char('(')?;
+            self.write_field_type(w, f)?;
+            w.write_str("),\n")?;
+        }
+        w.write_char('}')?;
+
+        if self.serde {
+            for f in choice.variants.iter().filter(|f| f.item_name.is_some()) {
+                w.write_str("\n\n")?;
+                self.write_items_module(w, &choice.name, f)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the enum of an EnumImpl, without any imports.
+    fn write_enum<W>(&mut self, w: &mut W, enumeration: &EnumImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let name = &enumeration.name;
+        let idents = variant_idents(&enumeration.variants);
+
+        write_allow_case(w, [name])?;
+        writeln!(w, "pub enum {} {{", name)?;
+        for ident in &idents {
+            w.write_str(INDENT)?;
+            w.write_str(ident)?;
+            w.write_str(",\n")?;
+        }
+        w.write_str("}\n\n")?;
+
+        // Conversion back to the value used in the schema.
+
+        writeln!(w, "impl {} {{", name)?;
+        w.write_str(INDENT)?;
+        w.write_str("pub fn as_str(&self) -> &'static str {\n")?;
+        w.write_str(INDENT)?;
+        w.write_str(INDENT)?;
+        w.write_str("match *self {\n")?;
+        for (ident, value) in idents.iter().zip(&enumeration.variants) {
+            w.write_str(INDENT)?;
+            w.write_str(INDENT)?;
+            w.write_str(INDENT)?;
+            writeln!(w, "Self::{} => {:?},", ident, value)?;
+        }
+        w.write_str(INDENT)?;
+        w.write_str(INDENT)?;
+        w.write_str("}\n")?;
+        w.write_str(INDENT)?;
+        w.write_str("}\n")?;
+        w.write_str("}\n\n")?;
+
+        // Conversion from the value used in the schema.
+
+        writeln!(w, "impl std::str::FromStr for {} {{", name)?;
+        w.write_str(INDENT)?;
+        w.write_str("type Err = String;\n\n")?;
+        w.write_str(INDENT)?;
+        w.write_str("fn from_str(s: &str) -> Result<Self, Self::Err> {\n")?;
+        w.write_str(INDENT)?;
+        w.write_str(INDENT)?;
+        w.write_str("match s {\n")?;
+        for (ident, value) in idents.iter().zip(&enumeration.variants) {
+            w.write_str(INDENT)?;
+            w.write_str(INDENT)?;
+            w.write_str(INDENT)?;
+            writeln!(w, "{:?} => Ok(Self::{}),", value, ident)?;
+        }
+        w.write_str(INDENT)?;
+        w.write_str(INDENT)?;
+        w.write_str(INDENT)?;
+        writeln!(w, "_ => Err(format!(\"Unknown {} `{{}}`\", s)),", name)?;
+        w.write_str(INDENT)?;
+        w.write_str(INDENT)?;
+        w.write_str("}\n")?;
+        w.write_str(INDENT)?;
+        w.write_str("}\n")?;
+        w.write_str("}\n\n")?;
+
+        writeln!(w, "impl std::fmt::Display for {} {{", name)?;
+        w.write_str(INDENT)?;
+        w.write_str("fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {\n")?;
+        w.write_str(INDENT)?;
+        w.write_str(INDENT)?;
+        w.write_str("f.write_str(self.as_str())\n")?;
+        w.write_str(INDENT)?;
+        w.write_str("}\n")?;
+        w.write_char('}')?;
+
+        // Written as strings, as serializers of XML rarely support unit variants.
+        if self.serde {
+            w.write_str("\n\n")?;
+            writeln!(w, "impl Serialize for {} {{", name)?;
+            for line in &[
+                "fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>",
+                "where",
+                "    S: serde::Serializer,",
+                "{",
+                "    serializer.serialize_str(self.as_str())",
+                "}",
+            ] {
+                write_line(w, 1, line)?;
+            }
+            w.write_str("}\n\n")?;
+            write_deserialize_from_str(w, name)?;
+        }
+
+        self.write_root_impl(w, name, &enumeration.roots)
+    }
+
+    /// Writes the struct of a NewtypeImpl, without any imports.
+    /// Facets are only checked for values written as strings or numbers,
+    /// patterns with the `regex` crate, compiled once per type.
+    fn write_newtype<W>(&mut self, w: &mut W, newtype: &NewtypeImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let name = &newtype.name;
+        let mut base = String::new();
+        self.write_type(&mut base, &newtype.base)?;
+
+        if self.serde {
+            w.write_str("#[derive(Deserialize)]\n")?;
+            writeln!(w, "#[serde(try_from = {:?})]", base)?;
+        }
+        write_allow_case(w, [name])?;
+        writeln!(w, "pub struct {}({});\n", name, base)?;
+
+        writeln!(w, "impl {} {{", name)?;
+        write_line(
+            w,
+            1,
+            "/// Checks that the value satisfies the facets of the schema.",
+        )?;
+        write_line(
+            w,
+            1,
+            &format!("pub fn new(value: {}) -> Result<Self, String> {{", base),
+        )?;
+        match self.value_kind(&newtype.base) {
+            ValueKind::Text => write_text_checks(w, newtype)?,
+            ValueKind::Integer if base.starts_with('u') => {
+                write_number_checks(w, newtype, |s| s.parse::<u128>().is_ok())?
+            }
+            ValueKind::Integer => write_number_checks(w, newtype, |s| s.parse::<i128>().is_ok())?,
+            ValueKind::Float => write_number_checks(w, newtype, |s| s.parse::<f64>().is_ok())?,
+            ValueKind::Other => (),
+        }
+        write_line(w, 2, "Ok(Self(value))")?;
+        write_line(w, 1, "}")?;
+        write_line(w, 0, "")?;
+        write_line(w, 1, &format!("pub fn value(&self) -> &{} {{", base))?;
+        write_line(w, 2, "&self.0")?;
+        write_line(w, 1, "}")?;
+        w.write_char('}')?;
+
+        // Lets the newtype be an item of a list or a member of a union.
+        if self.is_textual(&newtype.base) {
+            w.write_str("\n\n")?;
+            write_from_str(w, name, |w| {
+                write_line(
+                    w,
+                    2,
+                    &format!(
+                        "let value = s.parse().map_err(|_| format!(\"Invalid {} `{{}}`\", s))?;",
+                        name
+                    ),
+                )?;
+                write_line(w, 2, "Self::new(value)")
+            })?;
+            w.write_str("\n\n")?;
+            write_display(w, name, |w| {
+                write_line(w, 2, "std::fmt::Display::fmt(&self.0, f)")
+            })?;
+        }
+
+        if self.serde {
+            w.write_str("\n\n")?;
+            writeln!(w, "impl std::convert::TryFrom<{}> for {} {{", base, name)?;
+            write_line(w, 1, "type Error = String;")?;
+            write_line(w, 0, "")?;
+            write_line(
+                w,
+                1,
+                &format!(
+                    "fn try_from(value: {}) -> Result<Self, Self::Error> {{",
+                    base
+                ),
+            )?;
+            write_line(w, 2, "Self::new(value)")?;
+            write_line(w, 1, "}")?;
+            w.write_str("}\n\n")?;
+
+            writeln!(w, "impl Serialize for {} {{", name)?;
+            for line in &[
+                "fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>",
+                "where",
+                "    S: serde::Serializer,",
+                "{",
+                "    self.0.serialize(serializer)",
+                "}",
+            ] {
+                write_line(w, 1, line)?;
+            }
+            w.write_char('}')?;
+        }
+
+        self.write_root_impl(w, name, &newtype.roots)
+    }
+
+    /// Writes the struct of a ListImpl, without any imports.
+    /// Items are parsed and written with `FromStr` and `Display`.
+    fn write_list<W>(&mut self, w: &mut W, list: &ListImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let name = &list.name;
+        let mut item = String::new();
+        self.write_type(&mut item, &list.item)?;
+
+        write_allow_case(w, [name])?;
+        writeln!(w, "pub struct {}(pub Vec<{}>);\n", name, item)?;
+
+        write_from_str(w, name, |w| {
+            write_line(w, 2, "s.split_whitespace()")?;
+            write_line(
+                w,
+                3,
+                &format!(
+                    ".map(|item| item.parse().map_err(|_| format!(\"Invalid {} item `{{}}`\", item)))",
+                    name
+                ),
+            )?;
+            write_line(w, 3, ".collect::<Result<_, _>>()")?;
+            write_line(w, 3, ".map(Self)")
+        })?;
+        w.write_str("\n\n")?;
+        write_display(w, name, |w| {
+            write_line(w, 2, "for (i, item) in self.0.iter().enumerate() {")?;
+            write_line(w, 3, "if i > 0 {")?;
+            write_line(w, 4, "f.write_str(\" \")?;")?;
+            write_line(w, 3, "}")?;
+            write_line(w, 3, "std::fmt::Display::fmt(item, f)?;")?;
+            write_line(w, 2, "}")?;
+            write_line(w, 2, "Ok(())")
+        })?;
+
+        if self.serde {
+            w.write_str("\n\n")?;
+            writeln!(w, "impl Serialize for {} {{", name)?;
+            for line in &[
+                "fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>",
+                "where",
+                "    S: serde::Serializer,",
+                "{",
+                "    serializer.collect_str(self)",
+                "}",
+            ] {
+                write_line(w, 1, line)?;
+            }
+            w.write_str("}\n\n")?;
+            write_deserialize_from_str(w, name)?;
+        }
+
+        self.write_root_impl(w, name, &list.roots)
+    }
+
+    /// Writes the enum of a UnionImpl, without any imports.
+    /// Values are parsed by trying each member in turn.
+    fn write_union<W>(&mut self, w: &mut W, union: &UnionImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        let name = &union.name;
+        let names = union
+            .members
+            .iter()
+            .map(TypeName::to_string)
+            .collect::<Vec<_>>();
+        let idents = variant_idents(&names);
+
+        if self.serde {
+            w.write_str("#[derive(Serialize)]\n")?;
+            w.write_str("#[serde(untagged)]\n")?;
+        }
+
+        write_allow_case(w, [name])?;
+        writeln!(w, "pub enum {} {{", name)?;
+        for (ident, member) in idents.iter().zip(&union.members) {
+            w.write_str(INDENT)?;
+            write!(w, "{}(", ident)?;
+            self.write_type(w, member)?;
+            w.write_str("),\n")?;
+        }
+        w.write_str("}\n\n")?;
+
+        // Strings accept any value, so later members are never tried.
+        let is_string = |member: &TypeName| match member {
+            TypeName::Primitive(p) => !self.types.contains_key(p) && primitive_type(p) == "String",
+            _ => false,
+        };
+        write_from_str(w, name, |w| {
+            for (ident, member) in idents.iter().zip(&union.members) {
+                if is_string(member) {
+                    return write_line(w, 2, &format!("Ok(Self::{}(s.to_string()))", ident));
+                }
+                write_line(w, 2, "if let Ok(value) = s.parse() {")?;
+                write_line(w, 3, &format!("return Ok(Self::{}(value));", ident))?;
+                write_line(w, 2, "}")?;
+            }
+            write_line(
+                w,
+                2,
+                &format!("Err(format!(\"Invalid {} `{{}}`\", s))", name),
+            )
+        })?;
+        w.write_str("\n\n")?;
+        write_display(w, name, |w| {
+            write_line(w, 2, "match self {")?;
+            for ident in &idents {
+                write_line(
+                    w,
+                    3,
+                    &format!(
+                        "Self::{}(value) => std::fmt::Display::fmt(value, f),",
+                        ident
+                    ),
+                )?;
+            }
+            write_line(w, 2, "}")
+        })?;
+
+        if self.serde {
+            w.write_str("\n\n")?;
+            write_deserialize_from_str(w, name)?;
+        }
+
+        self.write_root_impl(w, name, &union.roots)
+    }
+
+    /// True if values written as `type_name` implement `FromStr` and `Display`,
+    /// as primitives and generated types do by default.
+    fn is_textual(&self, type_name: &TypeName) -> bool {
+        match type_name {
+            TypeName::Primitive(p) => !self.types.contains_key(p),
+            TypeName::Object(_) => true,
+            TypeName::Array(_) => false,
+        }
+    }
+
+    /// How the facets of a value written as `type_name` can be checked.
+    fn value_kind(&self, type_name: &TypeName) -> ValueKind {
+        let p = match type_name {
+            TypeName::Primitive(p) if !self.types.contains_key(p) => p,
+            _ => return ValueKind::Other,
+        };
+
+        match primitive_type(p) {
+            "String" => ValueKind::Text,
+            "f32" | "f64" => ValueKind::Float,
+            "bool" => ValueKind::Other,
+            _ => ValueKind::Integer,
+        }
+    }
+}
+
+impl CodeFormatter for RustFmt {
+    fn write_impl_file<W>(&mut self, w: &mut W, object: &ObjectImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n\n")?;
+        }
+
+        self.write_type_imports(w, object.fields.iter().map(|f| &f.type_name))?;
+        write_imports(
+            w,
+            object
+                .fields
+                .iter()
+                .filter_map(|f| f.type_name.import_statement())
+                .chain(self.root_imports(&object.roots))
+                .chain(self.reader_import(&object.fields)),
+        )?;
+
+        self.write_object(w, object)
+    }
+
+    fn write_abstract_file<W>(&mut self, w: &mut W, abstract_impl: &AbstractImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n\n")?;
+        }
+
+        write_imports(
+            w,
+            abstract_impl
+                .subtypes
+                .iter()
+                .map(String::as_str)
+                .chain(Some("xml_writer").filter(|_| self.serde)),
+        )?;
+
+        self.write_abstract(w, abstract_impl)
+    }
+
+    fn write_choice_file<W>(&mut self, w: &mut W, choice: &ChoiceImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n\n")?;
+        }
+
+        self.write_type_imports(w, choice.variants.iter().map(|f| &f.type_name))?;
+        write_imports(
+            w,
+            choice
+                .variants
+                .iter()
+                .filter_map(|f| f.type_name.import_statement()),
+        )?;
+
+        self.write_choice(w, choice)
+    }
+
+    fn write_newtype_file<W>(&mut self, w: &mut W, newtype: &NewtypeImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n\n")?;
+        }
+
+        self.write_type_imports(w, Some(&newtype.base))?;
+        write_imports(
+            w,
+            newtype
+                .base
+                .import_statement()
+                .into_iter()
+                .chain(self.root_imports(&newtype.roots)),
+        )?;
+
+        self.write_newtype(w, newtype)
+    }
+
+    fn write_list_file<W>(&mut self, w: &mut W, list: &ListImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n\n")?;
+        }
+
+        write_imports(
+            w,
+            list.item
+                .import_statement()
+                .into_iter()
+                .chain(self.root_imports(&list.roots)),
+        )?;
+
+        self.write_list(w, list)
+    }
+
+    fn write_union_file<W>(&mut self, w: &mut W, union: &UnionImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n\n")?;
+        }
+
+        write_imports(
+            w,
+            union
+                .members
+                .iter()
+                .filter_map(TypeName::import_statement)
+                .chain(self.root_imports(&union.roots)),
+        )?;
+
+        self.write_union(w, union)
+    }
+
+    fn write_enum_file<W>(&mut self, w: &mut W, enumeration: &EnumImpl) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n\n")?;
+        }
+
+        write_imports(w, self.root_imports(&enumeration.roots))?;
+
+        self.write_enum(w, enumeration)
+    }
+
+    fn write_type<W>(&mut self, w: &mut W, p: &TypeName) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        match p {
+            TypeName::Primitive(p) => match self.types.get(p) {
+                Some(mapping) => w.write_str(&mapping.rust_type),
+                None => w.write_str(primitive_type(p)),
+            },
+            // TypeName::Nullable(ref inner) => {
+            //     w.write_str("Option<")?;
+            //     self.write_type(w, inner)?;
+            //     w.write_str(">")
+            // }
+            TypeName::Array(ref inner) => {
+                w.write_str("Vec<")?;
+                self.write_type(w, inner)?;
+                w.write_str(">")
+            }
+            TypeName::Object(ref name) => w.write_str(&name),
+        }
+    }
+
+    fn file_name(&self, implementation: &Implementation) -> String {
+        format!(
+            "{}.rs",
+            module_ident(implementation).trim_start_matches("r#")
+        )
+    }
+
+    fn index_file_name(&self) -> String {
+        String::from("mod.rs")
+    }
+
+    fn write_combined_file<W>(
+        &mut self,
+        w: &mut W,
+        implementations: &[Implementation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        // All types live in the same file, so only external imports are needed.
+        if self.serde {
+            w.write_str("use serde::{Deserialize, Serialize};\n")?;
+        }
+
+        let types = implementations.iter().flat_map(|imp| match imp {
+            Implementation::Object(inner) => inner.fields.iter().map(|f| &f.type_name).collect(),
+            Implementation::Choice(inner) => inner.variants.iter().map(|f| &f.type_name).collect(),
+            Implementation::Newtype(inner) => vec![&inner.base],
+            _ => Vec::new(),
+        });
+        for import in self.type_imports(types) {
+            writeln!(w, "use {};", import)?;
+        }
+
+        for imp in implementations {
+            w.write_char('\n')?;
+            match imp {
+                Implementation::Enum(inner) => self.write_enum(w, inner)?,
+                Implementation::Object(inner) => self.write_object(w, inner)?,
+                Implementation::Abstract(inner) => self.write_abstract(w, inner)?,
+                Implementation::Choice(inner) => self.write_choice(w, inner)?,
+                Implementation::Newtype(inner) => self.write_newtype(w, inner)?,
+                Implementation::List(inner) => self.write_list(w, inner)?,
+                Implementation::Union(inner) => self.write_union(w, inner)?,
+            }
+            w.write_char('\n')?;
+        }
+
+        self.write_xml_writer(w)
+    }
+
+    fn write_index_file<W>(&mut self, w: &mut W, implementations: &[Implementation]) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        self.write_declarations(w, implementations)?;
+        self.write_xml_writer(w)
+    }
+
+    fn module_name(&self, namespace: &str) -> String {
+        let segment = namespace
+            .rsplit(['/', ':', '#'])
+            .find(|s| !s.is_empty())
+            .unwrap_or_default();
+
+        let ident = field_ident(segment);
+        match ident.chars().next() {
+            Some(c) if !c.is_ascii_digit() && !ident.starts_with("r#") => ident,
+            Some(_) => format!("ns_{}", ident.trim_start_matches("r#")),
+            None => String::from("namespace"),
+        }
+    }
+
+    fn write_namespace_index_file<W>(
+        &mut self,
+        w: &mut W,
+        implementations: &[Implementation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        // Makes the types of other namespaces visible to the files of this one.
+        w.write_str("#[allow(unused_imports)]\nuse super::*;\n\n")?;
+        self.write_declarations(w, implementations)
+    }
+
+    fn write_root_index_file<W>(
+        &mut self,
+        w: &mut W,
+        modules: &[String],
+        implementations: &[Implementation],
+    ) -> fmt::Result
+    where
+        W: fmt::Write,
+    {
+        for module in modules {
+            writeln!(w, "pub mod {};", module)?;
+        }
+
+        w.write_char('\n')?;
+
+        for module in modules {
+            writeln!(w, "pub use {}::*;", module)?;
+        }
+
+        if !implementations.is_empty() {
+            w.write_char('\n')?;
+            self.write_declarations(w, implementations)?;
+        }
+
+        self.write_xml_writer(w)
+    }
+}
+
+/// What a newtype's value is written as, deciding the facets checked.
+enum ValueKind {
+    Text,
+    Integer,
+    Float,
+    Other,
+}
+
+/// True if a field is written as nillable. Arrays are not, as an empty
+/// array tells as much as a nil one, nor are fields with a value.
+fn is_nillable(f: &Field) -> bool {
+    f.nillable && !matches!(f.type_name, TypeName::Array(_)) && field_value(f).is_none()
+}
+
+/// The fixed or default value of a field, unless it's an array.
+fn field_value(f: &Field) -> Option<&str> {
+    match f.type_name {
+        TypeName::Array(_) => None,
+        _ => f.fixed.as_deref().or(f.default.as_deref()),
+    }
+}
+
+/// The default or fixed value of an element, which it takes when empty.
+fn empty_value(f: &Field) -> Option<&str> {
+    field_value(f).filter(|_| f.kind == FieldKind::Element)
+}
+
+/// True if a field is written as an `Option`, as it may be absent and
+/// has no value to fall back on. Only attributes fall back on theirs.
+fn is_optional(f: &Field) -> bool {
+    !f.required && !is_repeated(f) && (field_value(f).is_none() || f.kind != FieldKind::Attribute)
+}
+
+/// True if a field is an array of repeated elements, which is empty
+/// rather than `None` when none of them are present.
+fn is_repeated(f: &Field) -> bool {
+    f.bounds.is_some() && f.item_name.is_none() && f.kind == FieldKind::Element
+}
+
+/// True if a field is a parameter of the `required` constructor.
+/// Fields with a fixed value are left out.
+fn is_parameter(f: &Field) -> bool {
+    f.required && (f.fixed.is_none() || field_value(f).is_none())
+}
+
+/// The name of the function returning the value of a field.
+fn default_fn(f: &Field) -> String {
+    format!("default_{}", field_ident(&f.name).trim_start_matches("r#"))
+}
+
+/// The name of the function reading an element with a value.
+fn deserialize_fn(f: &Field) -> String {
+    format!(
+        "deserialize_{}",
+        field_ident(&f.name).trim_start_matches("r#")
+    )
+}
+
+/// Writes the initializer of a field that isn't a constructor parameter.
+fn write_field_init<W>(w: &mut W, f: &Field) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    match field_value(f) {
+        Some(_) if !is_optional(f) => {
+            writeln!(w, "{}: Self::{}(),", field_ident(&f.name), default_fn(f))
+        }
+        _ if is_repeated(f) => writeln!(w, "{}: Vec::new(),", field_ident(&f.name)),
+        _ => writeln!(w, "{}: None,", field_ident(&f.name)),
+    }
+}
+
+/// Writes the checks of the white space, length and pattern facets.
+fn write_text_checks<W>(w: &mut W, newtype: &NewtypeImpl) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    let facets = &newtype.facets;
+    match facets.white_space {
+        Some(WhiteSpace::Replace) => write_line(
+            w,
+            2,
+            "let value = value.replace(|c| c == '\\t' || c == '\\n' || c == '\\r', \" \");",
+        )?,
+        Some(WhiteSpace::Collapse) => write_line(
+            w,
+            2,
+            "let value = value.split_whitespace().collect::<Vec<_>>().join(\" \");",
+        )?,
+        Some(WhiteSpace::Preserve) | None => (),
+    }
+
+    let mut lengths = Vec::new();
+    if let Some(n) = facets.length {
+        lengths.push(("!=", n, format!("exactly {}", n)));
+    }
+    if let Some(n) = facets.min_length {
+        lengths.push(("<", n, format!("at least {}", n)));
+    }
+    if let Some(n) = facets.max_length {
+        lengths.push((">", n, format!("at most {}", n)));
+    }
+    // Binary values are measured in the octets they decode to.
+    let (length, unit) = match newtype.base.primitive() {
+        Some(Primitive::HexBinary) => ("value.chars().count() / 2", "octets"),
+        Some(Primitive::Base64Binary) => (
+            "value.chars().filter(|c| !c.is_whitespace() && *c != '=').count() * 3 / 4",
+            "octets",
+        ),
+        _ => ("value.chars().count()", "characters"),
+    };
+    if !lengths.is_empty() {
+        write_line(w, 2, &format!("let length = {};", length))?;
+    }
+    for (op, n, message) in lengths {
+        write_check(
+            w,
+            &format!("length {} {}", op, n),
+            &format!("must have {} {}", message, unit),
+        )?;
+    }
+
+    if !facets.patterns.is_empty() {
+        let patterns = facets
+            .patterns
+            .iter()
+            .map(|p| format!("{:?}", format!("^(?:{})$", p)))
+            .collect::<Vec<_>>();
+        // Patterns are translated and validated when the model is created,
+        // so compiling them only fails if they are too big for the crate.
+        write_line(
+            w,
+            2,
+            "static PATTERNS: std::sync::OnceLock<Vec<regex::Regex>> = std::sync::OnceLock::new();",
+        )?;
+        write_line(w, 2, "let patterns = PATTERNS.get_or_init(|| {")?;
+        write_line(w, 3, &format!("[{}]", patterns.join(", ")))?;
+        write_line(w, 4, ".iter()")?;
+        write_line(w, 4, ".filter_map(|p| regex::Regex::new(p).ok())")?;
+        write_line(w, 4, ".collect()")?;
+        write_line(w, 2, "});")?;
+        write_check(
+            w,
+            "!patterns.iter().any(|p| p.is_match(&value))",
+            "does not match the pattern",
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes the checks of the bound and digit facets.
+/// Bounds that aren't valid literals of the type are left out.
+fn write_number_checks<W, F>(w: &mut W, newtype: &NewtypeImpl, is_literal: F) -> fmt::Result
+where
+    W: fmt::Write,
+    F: Fn(&str) -> bool,
+{
+    let facets = &newtype.facets;
+    let bounds = [
+        ("<", facets.min_inclusive.as_ref(), "less than"),
+        (">", facets.max_inclusive.as_ref(), "greater than"),
+    ];
+    for (op, bound, message) in bounds.iter() {
+        let bound = match bound.map(|b| b.trim()).filter(|b| is_literal(b)) {
+            Some(bound) => bound,
+            None => continue,
+        };
+        // Integers are written as floats for float values.
+        let literal = match (is_literal("0.5"), bound.contains(&['.', 'e', 'E'][..])) {
+            (true, false) => format!("{}.0", bound),
+            _ => bound.to_string(),
+        };
+        write_check(
+            w,
+            &format!("value {} {}", op, literal),
+            &format!("must not be {} {}", message, bound),
+        )?;
+    }
+
+    if facets.total_digits.is_none() && facets.fraction_digits.is_none() {
+        return Ok(());
+    }
+
+    write_line(w, 2, "let digits = value.to_string();")?;
+    if let Some(n) = facets.total_digits {
+        write_line(
+            w,
+            2,
+            "let total_digits = digits.trim_start_matches(|c| c == '-' || c == '0' || c == '.')",
+        )?;
+        write_line(w, 3, ".chars()")?;
+        write_line(w, 3, ".filter(char::is_ascii_digit)")?;
+        write_line(w, 3, ".count();")?;
+        write_check(
+            w,
+            &format!("total_digits > {}", n),
+            &format!("must have at most {} digits", n),
+        )?;
+    }
+    if let Some(n) = facets.fraction_digits {
+        write_line(
+            w,
+            2,
+            "let fraction_digits = digits.split('.').nth(1).map_or(0, str::len);",
+        )?;
+        write_check(
+            w,
+            &format!("fraction_digits > {}", n),
+            &format!("must have at most {} fraction digits", n),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Writes a `FromStr` impl with `String` errors, its body written by `body`.
+fn write_from_str<W, F>(w: &mut W, name: &str, body: F) -> fmt::Result
+where
+    W: fmt::Write,
+    F: FnOnce(&mut W) -> fmt::Result,
+{
+    writeln!(w, "impl std::str::FromStr for {} {{", name)?;
+    write_line(w, 1, "type Err = String;")?;
+    write_line(w, 0, "")?;
+    write_line(w, 1, "fn from_str(s: &str) -> Result<Self, Self::Err> {")?;
+    body(w)?;
+    write_line(w, 1, "}")?;
+    w.write_char('}')
+}
+
+/// Writes a `Display` impl, its body written by `body`.
+fn write_display<W, F>(w: &mut W, name: &str, body: F) -> fmt::Result
+where
+    W: fmt::Write,
+    F: FnOnce(&mut W) -> fmt::Result,
+{
+    writeln!(w, "impl std::fmt::Display for {} {{", name)?;
+    write_line(
+        w,
+        1,
+        "fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {",
+    )?;
+    body(w)?;
+    write_line(w, 1, "}")?;
+    w.write_char('}')
+}
+
+/// Writes a `Deserialize` impl parsing the value with `FromStr`.
+fn write_deserialize_from_str<W>(w: &mut W, name: &str) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    writeln!(w, "impl<'de> Deserialize<'de> for {} {{", name)?;
+    for line in &[
+        "fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>",
+        "where",
+        "    D: serde::Deserializer<'de>,",
+        "{",
+        "    String::deserialize(deserializer)?",
+        "        .parse()",
+        "        .map_err(serde::de::Error::custom)",
+        "}",
+    ] {
+        write_line(w, 1, line)?;
+    }
+    w.write_char('}')
+}
+
+/// Writes `Serialize` and `Deserialize` impls of an AbstractImpl.
+/// Variants are written as the newtype variants the XML writer gives an
+/// `xsi:type` attribute, read through `xml_writer::split_type` before the
+/// subtype reads the rest of the element.
+fn write_abstract_serde<W>(w: &mut W, abstract_impl: &AbstractImpl) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    let name = &abstract_impl.name;
+
+    writeln!(w, "impl Serialize for {} {{", name)?;
+    for line in &[
+        "fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>",
+        "where",
+        "    S: serde::Serializer,",
+        "{",
+        "    match self {",
+    ] {
+        write_line(w, 1, line)?;
+    }
+    for (i, subtype) in abstract_impl.subtypes.iter().enumerate() {
+        write_line(
+            w,
+            3,
+            &format!(
+                "Self::{}(value) => serializer.serialize_newtype_variant({:?}, {}, {:?}, value),",
+                subtype, name, i, subtype
+            ),
+        )?;
+    }
+    write_line(w, 2, "}")?;
+    write_line(w, 1, "}")?;
+    w.write_str("}\n\n")?;
+
+    writeln!(w, "impl<'de> Deserialize<'de> for {} {{", name)?;
+    for line in &[
+        "fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>",
+        "where",
+        "    D: serde::Deserializer<'de>,",
+        "{",
+        "    struct Visitor;",
+        "",
+        "    impl<'de> serde::de::Visitor<'de> for Visitor {",
+    ] {
+        write_line(w, 1, line)?;
+    }
+    write_line(w, 3, &format!("type Value = {};", name))?;
+    for line in &[
+        "",
+        "fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {",
+        "    f.write_str(\"an element with an `xsi:type` attribute\")",
+        "}",
+        "",
+        "fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>",
+        "where",
+        "    A: serde::de::MapAccess<'de>,",
+        "{",
+        "    let (subtype, rest) = xml_writer::split_type(map)?;",
+        "    match subtype.as_str() {",
+    ] {
+        write_line(w, 3, line)?;
+    }
+    for subtype in &abstract_impl.subtypes {
+        write_line(
+            w,
+            5,
+            &format!(
+                "{:?} => {}::deserialize(rest).map({}::{}),",
+                subtype, subtype, name, subtype
+            ),
+        )?;
+    }
+    let subtypes = abstract_impl
+        .subtypes
+        .iter()
+        .map(|s| format!("{:?}", s))
+        .collect::<Vec<_>>();
+    write_line(
+        w,
+        5,
+        &format!(
+            "other => Err(serde::de::Error::unknown_variant(other, &[{}])),",
+            subtypes.join(", ")
+        ),
+    )?;
+    for (depth, line) in &[
+        (4, "}"),
+        (3, "}"),
+        (2, "}"),
+        (0, ""),
+        (2, "deserializer.deserialize_map(Visitor)"),
+        (1, "}"),
+    ] {
+        write_line(w, *depth, line)?;
+    }
+    w.write_char('}')
+}
+
+/// Writes an early return of an error about the value if `condition` holds.
+fn write_check<W>(w: &mut W, condition: &str, message: &str) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    write_line(w, 2, &format!("if {} {{", condition))?;
+    write_line(
+        w,
+        3,
+        &format!("return Err(format!(\"`{{}}` {}\", value));", message),
+    )?;
+    write_line(w, 2, "}")
+}
+
+/// The Rust type a primitive is written as by default.
+/// Types without a fitting Rust type are kept as their lexical String.
+/// That includes `anyType`, whose content is read as text only unless
+/// it's mapped to a type reading elements.
+fn primitive_type(p: &Primitive) -> &'static str {
+    match p {
+        Primitive::Bool => "bool",
+        Primitive::Byte => "i8",
+        Primitive::Short => "i16",
+        Primitive::Int => "i32",
+        Primitive::Long
+        | Primitive::Integer
+        | Primitive::NonPositiveInteger
+        | Primitive::NegativeInteger => "i64",
+        Primitive::UnsignedByte => "u8",
+        Primitive::UnsignedShort => "u16",
+        Primitive::UnsignedInt => "u32",
+        Primitive::UnsignedLong | Primitive::NonNegativeInteger | Primitive::PositiveInteger => {
+            "u64"
+        }
+        Primitive::Float => "f32",
+        Primitive::Double | Primitive::Decimal => "f64",
+        _ => "String",
+    }
+}
+
+/// Types keep the names the schema gives them, which need not be
+/// UpperCamelCase, such as the `guid` of .NET schemas.
+fn write_allow_case<'a, W, I>(w: &mut W, names: I) -> fmt::Result
+where
+    W: fmt::Write,
+    I: IntoIterator<Item = &'a String>,
+{
+    let is_camel_case = |name: &str| !name.starts_with(char::is_lowercase) && !name.contains('_');
+    if !names.into_iter().all(|name| is_camel_case(name)) {
+        w.write_str("#[allow(non_camel_case_types)]\n")?;
+    }
+
+    Ok(())
+}
+
+/// Writes a `use super::{..}` importing the given types from the parent module.
+fn write_imports<'a, W, I>(w: &mut W, imports: I) -> fmt::Result
+where
+    W: fmt::Write,
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut imports = imports
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    if imports.is_empty() {
+        return Ok(());
+    }
+
+    imports.sort_unstable();
+
+    w.write_str("use super::{\n")?;
+    for s in imports {
+        w.write_str(INDENT)?;
+        w.write_str(s)?;
+        w.write_str(",\n")?;
+    }
+
+    w.write_str("};\n\n")
+}
+
+/// The number of items of a field written as a fixed size array.
+fn fixed_size(f: &Field) -> Option<u64> {
+    f.bounds
+        .and_then(|b| b.exact())
+        .filter(|n| (2..=MAX_FIXED_ARRAY).contains(n))
+}
+
+/// Writes a line indented `depth` times. Empty lines are not indented.
+fn write_line<W>(w: &mut W, depth: usize, line: &str) -> fmt::Result
+where
+    W: fmt::Write,
+{
+    if !line.is_empty() {
+        for _ in 0..depth {
+            w.write_str(INDENT)?;
+        }
+        w.write_str(line)?;
+    }
+    w.write_char('\n')
+}
+
+/// The module holding the serde functions of a field with `Field::item_name`.
+/// Prefixed by the owning type as several types can share a file.
+fn items_module(owner: &str, f: &Field) -> String {
+    format!(
+        "{}_{}",
+        owner.to_snake_case(),
+        field_ident(&f.name).trim_start_matches("r#")
+    )
+}
+
+/// The module an Implementation is declared in.
+/// Modules share namespace with types, so a type that is already
+/// snake_cased gets a suffixed module.
+fn module_ident(implementation: &Implementation) -> String {
+    let ident = field_ident(implementation.name());
+    if ident == implementation.name() {
+        format!("{}_impl", ident)
+    } else {
+        ident
+    }
+}
+
+/// Creates a valid Rust field name from a schema name.
+fn field_ident(name: &str) -> String {
+    let ident = name.to_snake_case();
+    match ident.as_str() {
+        "self" | "super" | "crate" => format!("{}_", ident),
+        s if KEYWORDS.contains(&s) => format!("r#{}", ident),
+        _ => ident,
+    }
+}
+
+/// Converts a snake_cased name to PascalCase the same way
+/// `#[serde(rename_all = "PascalCase")]` does.
+fn pascal_case(snake: &str) -> String {
+    let mut res = String::with_capacity(snake.len());
+    let mut capitalize = true;
+
+    for c in snake.chars() {
+        if c == '_' {
+            capitalize = true;
+        } else if capitalize {
+            res.push(c.to_ascii_uppercase());
+            capitalize = false;
+        } else {
+            res.push(c);
+        }
+    }
+
+    res
+}
+
+/// Creates unique and valid Rust variant names from enumeration values.
+/// Values that collide after conversion are suffixed by their position.
+fn variant_idents(values: &[String]) -> Vec<String> {
+    let bases = values
+        .iter()
+        .map(|value| {
+            let mut ident = value.to_camel_case();
+
+            if ident.is_empty() {
+                ident = String::from("Empty");
+            } else if ident.starts_with(|c: char| c.is_ascii_digit()) {
+                ident.insert(0, 'V');
+            } else if ident == "Self" {
+                ident.push_str("Value");
+            }
+
+            ident
+        })
+        .collect::<Vec<_>>();
+
+    // Suffixed idents must not take the ident of a later value either.
+    let all = bases.iter().cloned().collect::<HashSet<_>>();
+    let mut taken = HashSet::new();
+
+    bases
+        .into_iter()
+        .map(|base| {
+            let mut ident = base.clone();
+            let mut i = 0;
+            while taken.contains(&ident) || (i > 0 && all.contains(&ident)) {
+                i += 1;
+                ident = format!("{}{}", base, i);
+            }
+
+            taken.insert(ident.clone());
+            ident
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod xml_writer;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Facets, Primitive};
+
+    /// Declares a module `$name` with the items, along with their code as
+    /// `CODE`, to check generated code by compiling and running it.
+    macro_rules! compiled {
+        ($name:ident { $($item:item)* }) => {
+            #[allow(dead_code, unused_imports, clippy::all)]
+            mod $name {
+                use super::super::xml_writer;
+                use serde::{Deserialize, Serialize};
+
+                $($item)*
+
+                pub const CODE: &str = stringify!($($item)*);
+            }
+        };
+    }
+
+    /// The tokens of `code` without doc comments, which `stringify!`
+    /// writes as attributes, and without white space.
+    fn tokens(code: &str) -> String {
+        let mut code = code
+            .lines()
+            .filter(|line| !line.trim_start().starts_with("///"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        while let Some(start) = code.find("#[doc = r") {
+            let hashes = code[start + 9..].chars().take_while(|c| *c == '#').count();
+            let end = format!("\"{}]", "#".repeat(hashes));
+            let len = code[start..].find(&end).expect("end of doc") + end.len();
+            code.replace_range(start..start + len, "");
+        }
+
+        code.chars().filter(|c| !c.is_whitespace()).collect()
+    }
+
+    #[test]
+    fn test_generating_enum_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new();
+
+        let enumeration = EnumImpl {
+            name: "AccountingExportFormatType".into(),
+            roots: Vec::new(),
+            base: TypeName::Primitive(Primitive::String),
+            variants: vec!["Unknown".into(), "SIE4".into(), "CSV".into()],
+        };
+
+        f.write_enum_file(&mut buf, &enumeration).expect("Writing");
+
+        assert_eq!(
+            r#"
+pub enum AccountingExportFormatType {
+    Unknown,
+    Sie4,
+    Csv,
+}
+
+impl AccountingExportFormatType {
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Unknown => "Unknown",
+            Self::Sie4 => "SIE4",
+            Self::Csv => "CSV",
+        }
+    }
+}
+
+impl std::str::FromStr for AccountingExportFormatType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Unknown" => Ok(Self::Unknown),
+            "SIE4" => Ok(Self::Sie4),
+            "CSV" => Ok(Self::Csv),
+            _ => Err(format!("Unknown AccountingExportFormatType `{}`", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for AccountingExportFormatType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+"#
+            .trim(),
+            &buf
+        );
+    }
+
+    #[test]
+    fn test_generating_serde_struct_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let object = ObjectImpl {
+            name: "ImAStruct".into(),
+            base: None,
+            fields: vec![
+                Field::new("CreditorPublicId", Primitive::String),
+                Field::new("Type", Primitive::Int).optional(),
+            ],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        f.write_impl_file(&mut buf, &object).expect("Writing");
+
+        assert!(buf.starts_with(
+            r#"
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ImAStruct {
+    pub creditor_public_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<i32>,
+}
+"#
+            .trim_start()
+        ));
+
+        let mut buf = String::new();
+        let object = ObjectImpl {
+            name: "ImAStruct".into(),
+            base: None,
+            fields: vec![
+                Field::new("OCR", Primitive::String),
+                Field::new("Amount", Primitive::Double),
+            ],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        f.write_impl_file(&mut buf, &object).expect("Writing");
+
+        assert!(buf.starts_with(
+            r#"
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct ImAStruct {
+    #[serde(rename = "OCR")]
+    pub ocr: String,
+    #[serde(rename = "Amount")]
+    pub amount: f64,
+}
+"#
+            .trim_start()
+        ));
+    }
+
+    compiled!(amount {
+        #[derive(Serialize, Deserialize)]
+        pub struct Amount {
+            #[serde(rename = "Value")]
+            pub value: f64,
+            #[serde(rename(serialize = "@Currency", deserialize = "Currency"))]
+            pub currency: String,
+            #[serde(rename(serialize = "@Rate", deserialize = "Rate"), skip_serializing_if = "Option::is_none")]
+            pub rate: Option<f64>,
+        }
+
+        impl Amount {
+            pub fn required(
+                value: f64,
+                currency: String,
+            ) -> Self {
+                Self {
+                    value,
+                    currency,
+                    rate: None,
+                }
+            }
+        }
+    });
+
+    #[test]
+    fn test_generating_attribute_fields() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let object = ObjectImpl {
+            name: "Amount".into(),
+            base: None,
+            fields: vec![
+                Field::new("Value", Primitive::Double),
+                Field::new("Currency", Primitive::String).attribute(),
+                Field::new("Rate", Primitive::Double).attribute().optional(),
+            ],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        f.write_object(&mut buf, &object).expect("Writing");
+        assert_eq!(tokens(&buf), tokens(amount::CODE));
+
+        let xml = r#"<Amount Currency="SEK" Rate="0.5"><Value>2</Value></Amount>"#;
+        let amount: amount::Amount = serde_xml_rs::from_str(xml).expect("Reading");
+        assert_eq!(amount.value, 2.0);
+        assert_eq!(amount.currency, "SEK");
+        assert_eq!(amount.rate, Some(0.5));
+        assert!(xml_writer::to_string(&amount, "Amount")
+            .expect("Writing")
+            .ends_with(xml));
+
+        let xml = r#"<Amount Currency="SEK"><Value>2</Value></Amount>"#;
+        let amount: amount::Amount = serde_xml_rs::from_str(xml).expect("Reading");
+        assert_eq!(amount.rate, None);
+        assert!(xml_writer::to_string(&amount, "Amount")
+            .expect("Writing")
+            .ends_with(xml));
+    }
+
+    compiled!(invoice {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Invoice {
+            pub amount: f64,
+            pub status: Status,
+        }
+
+        impl Invoice {
+            pub fn required(
+                amount: f64,
+                status: Status,
+            ) -> Self {
+                Self {
+                    amount,
+                    status,
+                }
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Invoice")
+            }
+
+            pub fn to_credit_invoice_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "CreditInvoice")
+            }
+        }
+
+        pub enum Status {
+            Open,
+            Paid,
+        }
+
+        impl Status {
+            pub fn as_str(&self) -> &'static str {
+                match *self {
+                    Self::Open => "Open",
+                    Self::Paid => "Paid",
+                }
+            }
+        }
+
+        impl std::str::FromStr for Status {
+            type Err = String;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    "Open" => Ok(Self::Open),
+                    "Paid" => Ok(Self::Paid),
+                    _ => Err(format!("Unknown Status `{}`", s)),
+                }
+            }
+        }
+
+        impl std::fmt::Display for Status {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl Serialize for Status {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Status {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                String::deserialize(deserializer)?
+                    .parse()
+                    .map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl Status {
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "State")
+            }
+        }
+    });
+
+    #[test]
+    fn test_generating_root_element_helpers() {
+        let mut f = RustFmt::new().with_serde();
+
+        let object = ObjectImpl {
+            name: "Invoice".into(),
+            base: None,
+            fields: vec![
+                Field::new("Amount", Primitive::Double),
+                Field::new("Status", TypeName::object("Status")),
+            ],
+            unordered: false,
+            roots: vec!["Invoice".into(), "CreditInvoice".into()],
+        };
+        let enumeration = EnumImpl {
+            name: "Status".into(),
+            roots: vec!["State".into()],
+            base: TypeName::Primitive(Primitive::String),
+            variants: vec!["Open".into(), "Paid".into()],
+        };
+
+        let mut buf = String::new();
+        f.write_object(&mut buf, &object).expect("Writing");
+        f.write_enum(&mut buf, &enumeration).expect("Writing");
+        assert_eq!(tokens(&buf), tokens(invoice::CODE));
+        assert!(buf.contains(
+            "/// Reads a document whose root element is `Invoice` or `CreditInvoice`.\n"
+        ));
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><CreditInvoice><Amount>-1.5</Amount><Status>Paid</Status></CreditInvoice>"#;
+        let invoice = invoice::Invoice::from_xml_str(xml).expect("Reading");
+        assert_eq!(invoice.amount, -1.5);
+        assert_eq!(invoice.status.as_str(), "Paid");
+        assert_eq!(
+            invoice.to_credit_invoice_xml_string().expect("Writing"),
+            xml
+        );
+        assert!(invoice
+            .to_xml_string()
+            .expect("Writing")
+            .ends_with("<Invoice><Amount>-1.5</Amount><Status>Paid</Status></Invoice>"));
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><State>Open</State>"#;
+        let status = invoice::Status::from_xml_str(xml).expect("Reading");
+        assert_eq!(status.to_xml_string().expect("Writing"), xml);
+    }
+
+    #[test]
+    fn test_writing_root_helpers_only_with_serde_and_roots() {
+        let roots = vec![
+            String::from("Order"),
+            String::from("RushOrder"),
+            String::from("late-order"),
+        ];
+
+        let mut buf = String::new();
+        RustFmt::new()
+            .with_serde()
+            .write_root_impl(&mut buf, "Order", &roots)
+            .expect("Writing");
+        assert!(buf.starts_with("\n\nimpl Order {\n"));
+        assert!(buf.contains(
+            "/// Reads a document whose root element is `Order`, `RushOrder` or `late-order`.\n"
+        ));
+        assert!(buf.contains("    pub fn to_xml_string(&self)"));
+        assert!(buf.contains("    pub fn to_rush_order_xml_string(&self)"));
+        assert!(buf.contains("    pub fn to_late_order_xml_string(&self)"));
+        assert!(buf.contains("        xml_writer::to_string(self, \"late-order\")\n"));
+        assert!(buf.ends_with("    }\n}"));
+
+        buf.clear();
+        RustFmt::new()
+            .write_root_impl(&mut buf, "Order", &roots)
+            .expect("Writing");
+        RustFmt::new()
+            .with_serde()
+            .write_root_impl(&mut buf, "Order", &[])
+            .expect("Writing");
+        assert_eq!(buf, "");
+
+        assert_eq!(
+            RustFmt::new().with_serde().root_imports(&roots),
+            Some("xml_writer")
+        );
+        assert_eq!(RustFmt::new().with_serde().root_imports(&[]), None);
+        assert_eq!(RustFmt::new().root_imports(&roots), None);
+
+        let newtype = NewtypeImpl {
+            name: "OrderId".into(),
+            roots: vec!["Id".into()],
+            base: TypeName::Primitive(Primitive::String),
+            facets: Facets::default(),
+        };
+        buf.clear();
+        RustFmt::new()
+            .with_serde()
+            .write_newtype_file(&mut buf, &newtype)
+            .expect("Writing");
+        assert!(buf.starts_with(
+            "use serde::{Deserialize, Serialize};\n\nuse super::{\n    xml_writer,\n};\n\n"
+        ));
+        assert!(buf.ends_with(
+            "impl OrderId {\n    /// Reads a document whose root element is `Id`.\n    \
+             pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {\n        \
+             serde_xml_rs::from_str(s)\n    }\n\n    \
+             /// Writes a document whose root element is `Id`.\n    \
+             pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {\n        \
+             xml_writer::to_string(self, \"Id\")\n    }\n}"
+        ));
+    }
+
+    #[test]
+    fn test_generating_nillable_fields() {
+        let object = ObjectImpl {
+            name: "Debtor".into(),
+            base: None,
+            fields: vec![
+                Field::new("Name", Primitive::String).nillable(),
+                Field::new("Email", Primitive::String).nillable().optional(),
+                Field::new("Phone", TypeName::array(Primitive::String))
+                    .nillable()
+                    .optional(),
+            ],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        let write = |mut f: RustFmt| {
+            let mut buf = String::new();
+            f.write_impl_file(&mut buf, &object).expect("Writing");
+            buf
+        };
+
+        assert!(write(RustFmt::new()).starts_with(
+            r#"
+pub struct Debtor {
+    pub name: String,
+    pub email: Option<String>,
+    pub phone: Option<Vec<String>>,
+}
+"#
+            .trim_start()
+        ));
+
+        assert!(
+            write(RustFmt::new().nillable(NillableStyle::Option)).starts_with(
+                r#"
+pub struct Debtor {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<Vec<String>>,
+}
+
+impl Debtor {
+    pub fn required(
+        name: Option<String>,
+    ) -> Self {
+"#
+                .trim_start()
+            )
+        );
+
+        assert!(
+            write(RustFmt::new().nillable(NillableStyle::NestedOption)).starts_with(
+                r#"
+pub struct Debtor {
+    pub name: Option<String>,
+    pub email: Option<Option<String>>,
+    pub phone: Option<Vec<String>>,
+}
+"#
+                .trim_start()
+            )
+        );
+
+        assert!(
+            write(RustFmt::new().nillable(NillableStyle::Wrapper("crate::Nillable".into())))
+                .starts_with(
+                    r#"
+pub struct Debtor {
+    pub name: crate::Nillable<String>,
+    pub email: Option<crate::Nillable<String>>,
+    pub phone: Option<Vec<String>>,
+}
+"#
+                    .trim_start()
+                )
+        );
+    }
+
+    /// A type for `NillableStyle::Wrapper`.
+    #[derive(Debug, PartialEq)]
+    pub enum Nillable<T> {
+        Nil,
+        Value(T),
+    }
+
+    impl<T> From<Option<T>> for Nillable<T> {
+        fn from(value: Option<T>) -> Self {
+            value.map_or(Nillable::Nil, Nillable::Value)
+        }
+    }
+
+    impl<'a, T> From<&'a Nillable<T>> for Option<&'a T> {
+        fn from(value: &'a Nillable<T>) -> Self {
+            match value {
+                Nillable::Nil => None,
+                Nillable::Value(value) => Some(value),
+            }
+        }
+    }
+
+    compiled!(nil_option {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Debtor {
+            #[serde(with = "xml_writer::nil")]
+            pub name: Option<String>,
+            #[serde(with = "xml_writer::nil", default, skip_serializing_if = "Option::is_none")]
+            pub age: Option<i32>,
+            #[serde(with = "xml_writer::nil", default, skip_serializing_if = "Option::is_none")]
+            pub address: Option<Address>,
+        }
+
+        impl Debtor {
+            pub fn required(
+                name: Option<String>,
+            ) -> Self {
+                Self {
+                    name,
+                    age: None,
+                    address: None,
+                }
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Debtor")
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Address {
+            pub street: String,
+        }
+
+        impl Address {
+            pub fn required(
+                street: String,
+            ) -> Self {
+                Self {
+                    street,
+                }
+            }
+        }
+    });
+
+    compiled!(nil_nested_option {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Debtor {
+            #[serde(with = "xml_writer::nil")]
+            pub name: Option<String>,
+            #[serde(with = "xml_writer::nil::option", default, skip_serializing_if = "Option::is_none")]
+            pub age: Option<Option<i32>>,
+            #[serde(with = "xml_writer::nil::option", default, skip_serializing_if = "Option::is_none")]
+            pub address: Option<Option<Address>>,
+        }
+
+        impl Debtor {
+            pub fn required(
+                name: Option<String>,
+            ) -> Self {
+                Self {
+                    name,
+                    age: None,
+                    address: None,
+                }
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Debtor")
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Address {
+            pub street: String,
+        }
+
+        impl Address {
+            pub fn required(
+                street: String,
+            ) -> Self {
+                Self {
+                    street,
+                }
+            }
+        }
+    });
+
+    compiled!(nil_wrapper {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Debtor {
+            #[serde(serialize_with = "xml_writer::nil::wrapped::serialize::<String, _, _>", deserialize_with = "xml_writer::nil::wrapped::deserialize::<String, _, _>")]
+            pub name: super::Nillable<String>,
+            #[serde(serialize_with = "xml_writer::nil::wrapped::option::serialize::<i32, _, _>", deserialize_with = "xml_writer::nil::wrapped::option::deserialize::<i32, _, _>", default, skip_serializing_if = "Option::is_none")]
+            pub age: Option<super::Nillable<i32>>,
+            #[serde(serialize_with = "xml_writer::nil::wrapped::option::serialize::<Address, _, _>", deserialize_with = "xml_writer::nil::wrapped::option::deserialize::<Address, _, _>", default, skip_serializing_if = "Option::is_none")]
+            pub address: Option<super::Nillable<Address>>,
+        }
+
+        impl Debtor {
+            pub fn required(
+                name: super::Nillable<String>,
+            ) -> Self {
+                Self {
+                    name,
+                    age: None,
+                    address: None,
+                }
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Debtor")
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Address {
+            pub street: String,
+        }
+
+        impl Address {
+            pub fn required(
+                street: String,
+            ) -> Self {
+                Self {
+                    street,
+                }
+            }
+        }
+    });
+
+    /// The code of `nil_objects` written in `style`.
+    fn write_nil_objects(style: NillableStyle) -> String {
+        let mut f = RustFmt::new().with_serde().nillable(style);
+        let mut buf = String::new();
+        for object in nil_objects() {
+            f.write_object(&mut buf, &object).expect("Writing");
+        }
+        buf
+    }
+
+    #[test]
+    fn test_reading_and_writing_nil_elements() {
+        let declaration = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+        let xsi = r#"xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:nil="true""#;
+        let nil = format!(
+            r#"{0}<Debtor><Name {1}/><Age {1}/><Address {1}/></Debtor>"#,
+            declaration, xsi
+        );
+        let missing = format!(r#"{}<Debtor><Name {}/></Debtor>"#, declaration, xsi);
+        let given = format!(
+            "{}<Debtor><Name>Ann</Name><Age>42</Age><Address><Street>Main</Street></Address></Debtor>",
+            declaration
+        );
+
+        assert_eq!(
+            tokens(&write_nil_objects(NillableStyle::Option)),
+            tokens(nil_option::CODE)
+        );
+        let debtor = nil_option::Debtor::from_xml_str(&nil).expect("Reading");
+        assert_eq!(debtor.name, None);
+        assert_eq!(debtor.age, None);
+        assert!(debtor.address.is_none());
+        assert_eq!(debtor.to_xml_string().expect("Writing"), missing);
+        let debtor = nil_option::Debtor::from_xml_str(&given).expect("Reading");
+        assert_eq!(debtor.name.as_deref(), Some("Ann"));
+        assert_eq!(debtor.age, Some(42));
+        assert_eq!(debtor.to_xml_string().expect("Writing"), given);
+        let empty = format!("{}<Debtor><Name/></Debtor>", declaration);
+        let debtor = nil_option::Debtor::from_xml_str(&empty).expect("Reading");
+        assert_eq!(debtor.name.as_deref(), Some(""));
+        assert!(nil_option::Debtor::from_xml_str(&format!("{}<Debtor/>", declaration)).is_err());
+
+        assert_eq!(
+            tokens(&write_nil_objects(NillableStyle::NestedOption)),
+            tokens(nil_nested_option::CODE)
+        );
+        let debtor = nil_nested_option::Debtor::from_xml_str(&nil).expect("Reading");
+        assert_eq!(debtor.name, None);
+        assert_eq!(debtor.age, Some(None));
+        assert!(matches!(debtor.address, Some(None)));
+        assert_eq!(debtor.to_xml_string().expect("Writing"), nil);
+        let debtor = nil_nested_option::Debtor::from_xml_str(&missing).expect("Reading");
+        assert_eq!(debtor.age, None);
+        assert!(debtor.address.is_none());
+        assert_eq!(debtor.to_xml_string().expect("Writing"), missing);
+        let debtor = nil_nested_option::Debtor::from_xml_str(&given).expect("Reading");
+        assert_eq!(debtor.age, Some(Some(42)));
+        assert_eq!(debtor.to_xml_string().expect("Writing"), given);
+
+        assert_eq!(
+            tokens(&write_nil_objects(NillableStyle::Wrapper(
+                "super::Nillable".into()
+            ))),
+            tokens(nil_wrapper::CODE)
+        );
+        let debtor = nil_wrapper::Debtor::from_xml_str(&nil).expect("Reading");
+        assert_eq!(debtor.name, Nillable::Nil);
+        assert_eq!(debtor.age, Some(Nillable::Nil));
+        assert!(matches!(debtor.address, Some(Nillable::Nil)));
+        assert_eq!(debtor.to_xml_string().expect("Writing"), nil);
+        let debtor = nil_wrapper::Debtor::from_xml_str(&missing).expect("Reading");
+        assert_eq!(debtor.age, None);
+        assert_eq!(debtor.to_xml_string().expect("Writing"), missing);
+        let debtor = nil_wrapper::Debtor::from_xml_str(&given).expect("Reading");
+        assert_eq!(debtor.name, Nillable::Value(String::from("Ann")));
+        assert_eq!(debtor.age, Some(Nillable::Value(42)));
+        assert_eq!(debtor.to_xml_string().expect("Writing"), given);
+
+        // Attributes before `xsi:nil` are read too.
+        let xml = format!(
+            r#"{}<Debtor><Name Lang="en" xsi:nil="true" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"/><Address Id="1" xsi:nil="true" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"/></Debtor>"#,
+            declaration
+        );
+        let debtor = nil_nested_option::Debtor::from_xml_str(&xml).expect("Reading");
+        assert_eq!(debtor.name, None);
+        assert!(matches!(debtor.address, Some(None)));
+    }
+
+    fn nil_objects() -> Vec<ObjectImpl> {
+        vec![
+            ObjectImpl {
+                name: "Debtor".into(),
+                base: None,
+                fields: vec![
+                    Field::new("Name", Primitive::String).nillable(),
+                    Field::new("Age", Primitive::Int).nillable().optional(),
+                    Field::new("Address", TypeName::Object("Address".into()))
+                        .nillable()
+                        .optional(),
+                ],
+                unordered: false,
+                roots: vec!["Debtor".into()],
+            },
+            ObjectImpl {
+                name: "Address".into(),
+                base: None,
+                fields: vec![Field::new("Street", Primitive::String)],
+                unordered: false,
+                roots: Vec::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_generating_default_values() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let object = ObjectImpl {
+            name: "Payment".into(),
+            base: None,
+            fields: vec![
+                Field::new("Currency", TypeName::object("Currency")).with_default("SEK"),
+                Field::new("Amount", Primitive::Double)
+                    .optional()
+                    .with_default("1"),
+                Field::new("Version", Primitive::Int)
+                    .attribute()
+                    .with_fixed("2"),
+                Field::new("Note", Primitive::String).optional(),
+            ],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        f.write_impl_file(&mut buf, &object).expect("Writing");
+
+        assert_eq!(
+            buf,
+            r#"
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Currency,
+    xml_writer,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct Payment {
+    #[serde(rename = "Currency", deserialize_with = "Payment::deserialize_currency")]
+    pub currency: Currency,
+    #[serde(rename = "Amount", deserialize_with = "Payment::deserialize_amount", default, skip_serializing_if = "Option::is_none")]
+    pub amount: Option<f64>,
+    #[serde(rename(serialize = "@Version", deserialize = "Version"), default = "Payment::default_version")]
+    pub version: i32,
+    #[serde(rename = "Note", skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Payment {
+    pub fn required(
+        currency: Currency,
+    ) -> Self {
+        Self {
+            currency,
+            amount: None,
+            version: Self::default_version(),
+            note: None,
+        }
+    }
+
+    pub fn default_currency() -> Currency {
+        "SEK".parse().expect("valid value")
+    }
+
+    fn deserialize_currency<'de, D>(deserializer: D) -> Result<Currency, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        xml_writer::text_or_else(deserializer, Self::default_currency)
+    }
+
+    pub fn default_amount() -> f64 {
+        1.0
+    }
+
+    fn deserialize_amount<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        xml_writer::text_or_else(deserializer, Self::default_amount).map(Some)
+    }
+
+    pub fn default_version() -> i32 {
+        2
+    }
+}
+
+impl Default for Payment {
+    fn default() -> Self {
+        Self {
+            currency: Self::default_currency(),
+            amount: None,
+            version: Self::default_version(),
+            note: None,
+        }
+    }
+}
+"#
+            .trim()
+        );
+    }
+
+    compiled!(charge {
+        #[derive(Serialize, Deserialize)]
+        pub struct Charge {
+            #[serde(rename = "Amount", deserialize_with = "Charge::deserialize_amount")]
+            pub amount: f64,
+            #[serde(rename = "Note", deserialize_with = "Charge::deserialize_note", default, skip_serializing_if = "Option::is_none")]
+            pub note: Option<String>,
+            #[serde(rename(serialize = "@Currency", deserialize = "Currency"), default = "Charge::default_currency")]
+            pub currency: String,
+        }
+
+        impl Charge {
+            pub fn required(
+                amount: f64,
+            ) -> Self {
+                Self {
+                    amount,
+                    note: None,
+                    currency: Self::default_currency(),
+                }
+            }
+
+            pub fn default_amount() -> f64 {
+                1.5
+            }
+
+            fn deserialize_amount<'de, D>(deserializer: D) -> Result<f64, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                xml_writer::text_or_else(deserializer, Self::default_amount)
+            }
+
+            pub fn default_note() -> String {
+                String::from("none")
+            }
+
+            fn deserialize_note<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                xml_writer::text_or_else(deserializer, Self::default_note).map(Some)
+            }
+
+            pub fn default_currency() -> String {
+                String::from("SEK")
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Charge")
+            }
+        }
+
+        impl Default for Charge {
+            fn default() -> Self {
+                Self {
+                    amount: Self::default_amount(),
+                    note: None,
+                    currency: Self::default_currency(),
+                }
+            }
+        }
+    });
+
+    #[test]
+    fn test_reading_default_values() {
+        let object = ObjectImpl {
+            name: "Charge".into(),
+            base: None,
+            fields: vec![
+                Field::new("Amount", Primitive::Double).with_default("1.5"),
+                Field::new("Note", Primitive::String)
+                    .optional()
+                    .with_default("none"),
+                Field::new("Currency", Primitive::String)
+                    .attribute()
+                    .optional()
+                    .with_default("SEK"),
+            ],
+            unordered: false,
+            roots: vec!["Charge".into()],
+        };
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+        f.write_object(&mut buf, &object).expect("Writing");
+        assert_eq!(tokens(&buf), tokens(charge::CODE));
+
+        // Empty elements take their default, missing ones don't.
+        let charge = charge::Charge::from_xml_str("<Charge><Amount/></Charge>").expect("Reading");
+        assert_eq!(charge.amount, 1.5);
+        assert_eq!(charge.note, None);
+        assert_eq!(charge.currency, "SEK");
+        assert!(charge::Charge::from_xml_str("<Charge />").is_err());
+
+        let charge = charge::Charge::from_xml_str(
+            r#"<Charge Currency="EUR"><Amount></Amount><Note/></Charge>"#,
+        )
+        .expect("Reading");
+        assert_eq!(charge.amount, 1.5);
+        assert_eq!(charge.note.as_deref(), Some("none"));
+        assert_eq!(charge.currency, "EUR");
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><Charge Currency="SEK"><Amount>2</Amount><Note>Paid</Note></Charge>"#;
+        let charge = charge::Charge::from_xml_str(xml).expect("Reading");
+        assert_eq!(charge.amount, 2.0);
+        assert_eq!(charge.note.as_deref(), Some("Paid"));
+        assert_eq!(charge.to_xml_string().expect("Writing"), xml);
+    }
+
+    #[test]
+    fn test_generating_choice_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let choice = ChoiceImpl {
+            name: "PaymentChoice".into(),
+            variants: vec![
+                Field::new("Iban", Primitive::String),
+                Field::new("bankgiro", TypeName::object("Bankgiro")),
+                Field::new("Reference", TypeName::array(Primitive::String)).optional(),
+            ],
+        };
+
+        f.write_choice_file(&mut buf, &choice).expect("Writing");
+
+        assert_eq!(
+            buf,
+            r#"
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Bankgiro,
+};
+
+#[derive(Serialize, Deserialize)]
+pub enum PaymentChoice {
+    Iban(String),
+    #[serde(rename = "bankgiro")]
+    Bankgiro(Bankgiro),
+    Reference(Option<Vec<String>>),
+}"#
+            .trim_start()
+        );
+
+        let object = ObjectImpl {
+            name: "Payment".into(),
+            base: None,
+            fields: vec![Field::new("Choice", TypeName::object("PaymentChoice"))
+                .choice()
+                .optional()],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        buf.clear();
+        f.write_impl_file(&mut buf, &object).expect("Writing");
+
+        assert!(buf.contains(
+            r#"
+pub struct Payment {
+    #[serde(rename = "$value", skip_serializing_if = "Option::is_none")]
+    pub choice: Option<PaymentChoice>,
+}
+"#
+        ));
+    }
+
+    compiled!(payment {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Payment {
+            pub amount: f64,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub iban: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            pub bankgiro: Option<String>,
+        }
+
+        impl Payment {
+            pub fn required(
+                amount: f64,
+            ) -> Self {
+                Self {
+                    amount,
+                    iban: None,
+                    bankgiro: None,
+                }
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Payment")
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        pub struct Transfer {
+            #[serde(rename = "$value")]
+            pub choice: TransferChoice,
+            #[serde(rename(serialize = "@Currency", deserialize = "Currency"), skip_serializing_if = "Option::is_none")]
+            pub currency: Option<String>,
+        }
+
+        impl Transfer {
+            pub fn required(
+                choice: TransferChoice,
+            ) -> Self {
+                Self {
+                    choice,
+                    currency: None,
+                }
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Transfer")
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        pub enum TransferChoice {
+            Iban(String),
+            Bankgiro(String),
+        }
+    });
+
+    #[test]
+    fn test_generating_choices_with_siblings() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="Payment" type="Payment" />
+  <xs:element name="Transfer" type="Transfer" />
+  <xs:complexType name="Payment">
+    <xs:sequence>
+      <xs:element name="Amount" type="xs:double" />
+      <xs:choice>
+        <xs:element name="Iban" type="xs:string" />
+        <xs:element name="Bankgiro" type="xs:string" />
+      </xs:choice>
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Transfer">
+    <xs:choice>
+      <xs:element name="Iban" type="xs:string" />
+      <xs:element name="Bankgiro" type="xs:string" />
+    </xs:choice>
+    <xs:attribute name="Currency" type="xs:string" />
+  </xs:complexType>
+</xs:schema>
+"#;
+        let xml_schema: crate::xml::Schema = crate::xml::de(s.as_bytes()).expect("Parsing");
+        let models = crate::ModelSchema::create_from_xml(xml_schema).expect("Creating ModelSchema");
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+        for imp in &models.implementations {
+            match imp {
+                Implementation::Object(o) => f.write_object(&mut buf, o).expect("Writing"),
+                Implementation::Choice(c) => f.write_choice(&mut buf, c).expect("Writing"),
+                _ => (),
+            }
+        }
+        assert_eq!(tokens(&buf), tokens(payment::CODE));
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><Payment><Amount>1.5</Amount><Bankgiro>123-4567</Bankgiro></Payment>"#;
+        let payment = payment::Payment::from_xml_str(xml).expect("Reading");
+        assert_eq!(payment.amount, 1.5);
+        assert_eq!(payment.iban, None);
+        assert_eq!(payment.bankgiro.as_deref(), Some("123-4567"));
+        assert_eq!(payment.to_xml_string().expect("Writing"), xml);
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><Transfer Currency="SEK"><Iban>SE35</Iban></Transfer>"#;
+        let transfer = payment::Transfer::from_xml_str(xml).expect("Reading");
+        assert!(
+            matches!(transfer.choice, payment::TransferChoice::Iban(ref iban) if iban == "SE35")
+        );
+        assert_eq!(transfer.to_xml_string().expect("Writing"), xml);
+    }
+
+    #[test]
+    fn test_generating_serde_enum_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let enumeration = EnumImpl {
+            name: "AccountingExportFormatType".into(),
+            roots: Vec::new(),
+            base: TypeName::Primitive(Primitive::String),
+            variants: vec!["Unknown".into(), "SIE4".into()],
+        };
+
+        f.write_enum_file(&mut buf, &enumeration).expect("Writing");
+
+        assert!(buf.starts_with(
+            r#"
+use serde::{Deserialize, Serialize};
+
+pub enum AccountingExportFormatType {
+    Unknown,
+    Sie4,
+}
+"#
+            .trim_start()
+        ));
+        assert!(buf.contains("            Self::Sie4 => \"SIE4\",\n"));
+        assert!(buf.contains(
+            r#"
+impl Serialize for AccountingExportFormatType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for AccountingExportFormatType {
+"#
+        ));
+    }
+
+    #[test]
+    fn test_generating_enum_with_colliding_values() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        // `A` is suffixed, but not as `A1`, which a later value takes.
+        let enumeration = EnumImpl {
+            name: "Grade".into(),
+            roots: Vec::new(),
+            base: TypeName::Primitive(Primitive::String),
+            variants: vec!["a".into(), "A".into(), "A1".into()],
+        };
+
+        f.write_enum(&mut buf, &enumeration).expect("Writing");
+
+        assert!(buf.starts_with("pub enum Grade {\n    A,\n    A2,\n    A1,\n}"));
+        assert!(buf.contains("            Self::A => \"a\",\n"));
+        assert!(buf.contains("            Self::A2 => \"A\",\n"));
+        assert!(buf.contains("            Self::A1 => \"A1\",\n"));
+    }
+
+    #[test]
+    fn test_generating_bounded_arrays() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new();
+
+        let object = ObjectImpl {
+            name: "ImAStruct".into(),
+            base: None,
+            fields: vec![
+                Field::new("Address", TypeName::array(Primitive::String)).bounded(2, Some(2)),
+                Field::new("Reference", TypeName::array(Primitive::Int)).bounded(1, Some(5)),
+                Field::new("Record", TypeName::array(Primitive::Int))
+                    .optional()
+                    .bounded(0, Some(3)),
+                Field::new("Row", TypeName::array(Primitive::Int))
+                    .optional()
+                    .bounded(0, None),
+            ],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        f.write_impl_file(&mut buf, &object).expect("Writing");
+
+        assert_eq!(
+            r#"
+pub struct ImAStruct {
+    pub address: [String; 2],
+    pub reference: Vec<i32>,
+    pub record: Vec<i32>,
+    pub row: Vec<i32>,
+}
+
+impl ImAStruct {
+    pub fn required(
+        address: [String; 2],
+        reference: Vec<i32>,
+    ) -> Self {
+        Self {
+            address,
+            reference,
+            record: Vec::new(),
+            row: Vec::new(),
+        }
+    }
+
+    /// Checks the number of items in bounded arrays.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=5).contains(&self.reference.len()) {
+            return Err(String::from("`Reference` must have between 1 and 5 items"));
+        }
+        if !(0..=3).contains(&self.record.len()) {
+            return Err(String::from("`Record` must have between 0 and 3 items"));
+        }
+        Ok(())
+    }
+}
+"#
+            .trim(),
+            &buf
+        );
+    }
+
+    compiled!(table {
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Table {
+            pub name: String,
+            #[serde(default)]
+            pub row: Vec<String>,
+        }
+
+        impl Table {
+            pub fn required(
+                name: String,
+            ) -> Self {
+                Self {
+                    name,
+                    row: Vec::new(),
+                }
+            }
+
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Table")
+            }
+        }
+    });
+
+    #[test]
+    fn test_generating_optional_repeated_elements() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let object = ObjectImpl {
+            name: "Table".into(),
+            base: None,
+            fields: vec![
+                Field::new("Name", Primitive::String),
+                Field::new("Row", TypeName::array(Primitive::String))
+                    .optional()
+                    .bounded(0, None),
+            ],
+            unordered: false,
+            roots: vec!["Table".into()],
+        };
+
+        f.write_object(&mut buf, &object).expect("Writing");
+        assert_eq!(tokens(&buf), tokens(table::CODE));
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><Table><Name>Empty</Name></Table>"#;
+        let table = table::Table::from_xml_str(xml).expect("Reading");
+        assert!(table.row.is_empty());
+        assert_eq!(table.to_xml_string().expect("Writing"), xml);
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><Table><Name>Full</Name><Row>a</Row><Row>b</Row></Table>"#;
+        let table = table::Table::from_xml_str(xml).expect("Reading");
+        assert_eq!(table.row, vec!["a", "b"]);
+        assert_eq!(table.to_xml_string().expect("Writing"), xml);
+    }
+
+    #[test]
+    fn test_generating_wrapped_array_field() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let object = ObjectImpl {
+            name: "ImAStruct".into(),
+            base: None,
+            fields: vec![Field::new("Rows", TypeName::array(Primitive::String)).items_named("Row")],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        f.write_impl_file(&mut buf, &object).expect("Writing");
+
+        assert!(buf.contains(
+            r#"
+pub struct ImAStruct {
+    #[serde(with = "im_a_struct_rows")]
+    pub rows: Vec<String>,
+}
+"#
+        ));
+
+        assert!(buf.ends_with(
+            r#"
+mod im_a_struct_rows {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    #[derive(Serialize)]
+    struct Items<'a> {
+        #[serde(rename = "Row")]
+        items: &'a [String],
+    }
+
+    #[derive(Deserialize)]
+    struct OwnedItems {
+        #[serde(rename = "Row", default)]
+        items: Vec<String>,
+    }
+
+    pub fn serialize<S>(items: &Vec<String>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Items { items }.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        OwnedItems::deserialize(deserializer).map(|i| i.items)
+    }
+}"#
+        ));
+    }
+
+    compiled!(animal {
+        pub enum Animal {
+            Cat(Cat),
+            Dog(Dog),
+        }
+
+        impl Serialize for Animal {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                match self {
+                    Self::Cat(value) => serializer.serialize_newtype_variant("Animal", 0, "Cat", value),
+                    Self::Dog(value) => serializer.serialize_newtype_variant("Animal", 1, "Dog", value),
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for Animal {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                struct Visitor;
+
+                impl<'de> serde::de::Visitor<'de> for Visitor {
+                    type Value = Animal;
+
+                    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                        f.write_str("an element with an `xsi:type` attribute")
+                    }
+
+                    fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+                    where
+                        A: serde::de::MapAccess<'de>,
+                    {
+                        let (subtype, rest) = xml_writer::split_type(map)?;
+                        match subtype.as_str() {
+                            "Cat" => Cat::deserialize(rest).map(Animal::Cat),
+                            "Dog" => Dog::deserialize(rest).map(Animal::Dog),
+                            other => Err(serde::de::Error::unknown_variant(other, &["Cat", "Dog"])),
+                        }
+                    }
+                }
+
+                deserializer.deserialize_map(Visitor)
+            }
+        }
+
+        impl Animal {
+            pub fn from_xml_str(s: &str) -> Result<Self, serde_xml_rs::Error> {
+                serde_xml_rs::from_str(s)
+            }
+
+            pub fn to_xml_string(&self) -> Result<String, xml_writer::Error> {
+                xml_writer::to_string(self, "Pet")
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        pub struct Cat {
+            pub name: String,
+            pub lives: i32,
+        }
+
+        impl Cat {
+            pub fn required(
+                name: String,
+                lives: i32,
+            ) -> Self {
+                Self {
+                    name,
+                    lives,
+                }
+            }
+        }
+
+        #[derive(Serialize, Deserialize)]
+        pub struct Dog {
+            #[serde(rename = "Name")]
+            pub name: String,
+            #[serde(rename = "Good")]
+            pub good: bool,
+            #[serde(rename(serialize = "@Id", deserialize = "Id"), skip_serializing_if = "Option::is_none")]
+            pub id: Option<i32>,
+        }
+
+        impl Dog {
+            pub fn required(
+                name: String,
+                good: bool,
+            ) -> Self {
+                Self {
+                    name,
+                    good,
+                    id: None,
+                }
+            }
+        }
+    });
+
+    #[test]
+    fn test_generating_abstract_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let abstract_impl = AbstractImpl {
+            name: "Animal".into(),
+            roots: vec!["Pet".into()],
+            subtypes: vec!["Cat".into(), "Dog".into()],
+        };
+
+        f.write_abstract_file(&mut buf, &abstract_impl)
+            .expect("Writing");
+        assert!(buf.starts_with(
+            r#"
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Cat,
+    Dog,
+    xml_writer,
+};
+
+pub enum Animal {
+"#
+            .trim_start()
+        ));
+
+        let subtype = |name: &str, field: Field| ObjectImpl {
+            name: name.into(),
+            base: Some("Animal".into()),
+            fields: vec![Field::new("Name", Primitive::String), field],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        buf.clear();
+        f.write_abstract(&mut buf, &abstract_impl).expect("Writing");
+        f.write_object(
+            &mut buf,
+            &subtype("Cat", Field::new("Lives", Primitive::Int)),
+        )
+        .expect("Writing");
+        let mut dog = subtype("Dog", Field::new("Good", Primitive::Bool));
+        dog.fields
+            .push(Field::new("Id", Primitive::Int).attribute().optional());
+        f.write_object(&mut buf, &dog).expect("Writing");
+        assert_eq!(tokens(&buf), tokens(animal::CODE));
+
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?><Pet xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Dog"><Name>Rex</Name><Good>true</Good></Pet>"#;
+        match animal::Animal::from_xml_str(xml).expect("Reading") {
+            animal::Animal::Dog(ref dog) => {
+                assert_eq!((dog.name.as_str(), dog.good), ("Rex", true))
+            }
+            _ => panic!("Expected a Dog"),
+        }
+        let pet = animal::Animal::from_xml_str(xml).expect("Reading");
+        assert_eq!(pet.to_xml_string().expect("Writing"), xml);
+
+        // Attributes may come before the type.
+        let xml = r#"<Pet Id="7" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="Dog"><Name>Rex</Name><Good>1</Good></Pet>"#;
+        match animal::Animal::from_xml_str(xml).expect("Reading") {
+            animal::Animal::Dog(ref dog) => {
+                assert_eq!(
+                    (dog.name.as_str(), dog.good, dog.id),
+                    ("Rex", true, Some(7))
+                )
+            }
+            _ => panic!("Expected a Dog"),
+        }
+
+        let xml = r#"<Pet xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="zoo:Fox"><Name>Ed</Name></Pet>"#;
+        let err = animal::Animal::from_xml_str(xml)
+            .err()
+            .expect("Unknown type");
+        assert!(err.to_string().contains("unknown variant `Fox`"));
+
+        #[derive(serde::Deserialize)]
+        struct Owner {
+            #[serde(rename = "Pet")]
+            pets: Vec<animal::Animal>,
+        }
+
+        let xml = r#"
+<Owner xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <Pet xsi:type="Cat"><Name>Tom</Name><Lives>9</Lives></Pet>
+  <Pet xsi:type="Dog"><Name>Rex</Name><Good>false</Good></Pet>
+</Owner>"#;
+        let owner: Owner = serde_xml_rs::from_str(xml).expect("Reading");
+        match owner.pets.as_slice() {
+            [animal::Animal::Cat(cat), animal::Animal::Dog(dog)] => {
+                assert_eq!((cat.name.as_str(), cat.lives), ("Tom", 9));
+                assert_eq!((dog.name.as_str(), dog.good), ("Rex", false));
+            }
+            _ => panic!("Expected a Cat and a Dog"),
+        }
+        assert!(
+            serde_xml_rs::from_str::<Owner>("<Owner><Pet><Name>Rex</Name></Pet></Owner>").is_err()
+        );
+    }
+
+    #[test]
+    fn test_generating_combined_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let implementations = vec![
+            Implementation::Object(ObjectImpl {
+                name: "AccountingExportCreation".into(),
+                base: None,
+                fields: vec![Field::new(
+                    "Format",
+                    TypeName::object("AccountingExportFormatType"),
+                )],
+                unordered: false,
+                roots: Vec::new(),
+            }),
+            Implementation::Enum(EnumImpl {
+                name: "AccountingExportFormatType".into(),
+                roots: Vec::new(),
+                base: TypeName::Primitive(Primitive::String),
+                variants: vec!["Unknown".into()],
+            }),
+        ];
+
+        f.write_combined_file(&mut buf, &implementations)
+            .expect("Writing");
+
+        assert!(buf.starts_with(
+            r#"
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct AccountingExportCreation {
+    pub format: AccountingExportFormatType,
+}
+"#
+            .trim_start()
+        ));
+
+        assert!(!buf.contains("use super"));
+        assert!(buf.contains("\npub enum AccountingExportFormatType {\n"));
+        assert!(buf.contains("\n#[allow(dead_code)]\nmod xml_writer {\n"));
+        assert!(!buf.contains("mod tests"));
+    }
+
+    #[test]
+    fn test_generating_index_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new();
+
+        let implementations = vec![
+            Implementation::Object(ObjectImpl {
+                name: "AccountingExportCreation".into(),
+                base: None,
+                fields: vec![],
+                unordered: false,
+                roots: Vec::new(),
+            }),
+            Implementation::Enum(EnumImpl {
+                name: "Type".into(),
+                roots: Vec::new(),
+                base: TypeName::Primitive(Primitive::String),
+                variants: vec![],
+            }),
+        ];
+
+        f.write_index_file(&mut buf, &implementations)
+            .expect("Writing");
+
+        assert_eq!(
+            r#"
+mod accounting_export_creation;
+mod r#type;
+
+pub use accounting_export_creation::AccountingExportCreation;
+pub use r#type::Type;
+"#
+            .trim_start(),
+            &buf
+        );
+
+        assert_eq!(
+            f.file_name(&implementations[0]),
+            "accounting_export_creation.rs"
+        );
+        assert_eq!(f.file_name(&implementations[1]), "type.rs");
+    }
+
+    #[test]
+    fn test_identifiers() {
+        assert_eq!(field_ident("CreditorPublicId"), "creditor_public_id");
+        assert_eq!(field_ident("Type"), "r#type");
+        assert_eq!(field_ident("Self"), "self_");
+
+        let values = ["a-b", "A_B", "1st", "", "self"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variant_idents(&values),
+            vec!["AB", "AB1", "V1st", "Empty", "SelfValue"]
+        );
+
+        let values = ["a", "A", "A1"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(variant_idents(&values), vec!["A", "A2", "A1"]);
+
+        assert_eq!(pascal_case("creditor_public_id"), "CreditorPublicId");
+        assert_eq!(pascal_case("ocr"), "Ocr");
+    }
+
+    #[test]
+    fn test_writing_primitive_types() {
+        let mut f = RustFmt::new();
+        let write = |f: &mut RustFmt, p: Primitive| {
+            let mut buf = String::new();
+            f.write_type(&mut buf, &TypeName::Primitive(p))
+                .expect("Writing");
+            buf
+        };
+
+        assert_eq!(write(&mut f, Primitive::Byte), "i8");
+        assert_eq!(write(&mut f, Primitive::UnsignedShort), "u16");
+        assert_eq!(write(&mut f, Primitive::PositiveInteger), "u64");
+        assert_eq!(write(&mut f, Primitive::Float), "f32");
+        assert_eq!(write(&mut f, Primitive::Decimal), "f64");
+        assert_eq!(write(&mut f, Primitive::Date), "String");
+        assert_eq!(write(&mut f, Primitive::HexBinary), "String");
+        assert_eq!(write(&mut f, Primitive::AnyType), "String");
+    }
+
+    #[test]
+    fn test_generating_mapped_types() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new()
+            .with_serde()
+            .map_type(
+                Primitive::DateTime,
+                TypeMapping::new("DateTime<Utc>").import("chrono::{DateTime, Utc}"),
+            )
+            .map_type(
+                Primitive::Base64Binary,
+                TypeMapping::new("Vec<u8>").serde_with("crate::base64"),
+            );
+
+        let object = ObjectImpl {
+            name: "Attachment".into(),
+            base: None,
+            fields: vec![
+                Field::new("Created", Primitive::DateTime),
+                Field::new("Content", Primitive::Base64Binary).optional(),
+            ],
+            unordered: false,
+            roots: Vec::new(),
+        };
+
+        f.write_impl_file(&mut buf, &object).expect("Writing");
+
+        assert_eq!(
+            r#"
+use serde::{Deserialize, Serialize};
+
+use chrono::{DateTime, Utc};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Attachment {
+    pub created: DateTime<Utc>,
+    #[serde(with = "crate::base64::option", default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<Vec<u8>>,
+}
+"#
+            .trim(),
+            buf.split("\n\nimpl").next().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generating_newtype_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new().with_serde();
+
+        let newtype = NewtypeImpl {
+            name: "Ocr".into(),
+            roots: Vec::new(),
+            base: TypeName::Primitive(Primitive::String),
+            facets: Facets {
+                patterns: vec!["[0-9]+".into()],
+                max_length: Some(25),
+                white_space: Some(WhiteSpace::Collapse),
+                ..Facets::default()
+            },
+        };
+
+        f.write_newtype_file(&mut buf, &newtype).expect("Writing");
+
+        assert_eq!(
+            r#"
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct Ocr(String);
+
+impl Ocr {
+    /// Checks that the value satisfies the facets of the schema.
+    pub fn new(value: String) -> Result<Self, String> {
+        let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+        let length = value.chars().count();
+        if length > 25 {
+            return Err(format!("`{}` must have at most 25 characters", value));
+        }
+        static PATTERNS: std::sync::OnceLock<Vec<regex::Regex>> = std::sync::OnceLock::new();
+        let patterns = PATTERNS.get_or_init(|| {
+            ["^(?:[0-9]+)$"]
+                .iter()
+                .filter_map(|p| regex::Regex::new(p).ok())
+                .collect()
+        });
+        if !patterns.iter().any(|p| p.is_match(&value)) {
+            return Err(format!("`{}` does not match the pattern", value));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> &String {
+        &self.0
+    }
+}
+"#
+            .trim(),
+            buf.split("\n\nimpl std::str::FromStr").next().unwrap()
+        );
+
+        buf.clear();
+        let small = NewtypeImpl {
+            name: "Small".into(),
+            roots: Vec::new(),
+            base: TypeName::Primitive(Primitive::UnsignedByte),
+            facets: Facets {
+                min_inclusive: Some("-1".into()),
+                max_inclusive: Some("10".into()),
+                ..Facets::default()
+            },
+        };
+        f.write_newtype(&mut buf, &small).expect("Writing");
+        assert!(!buf.contains("value < -1"));
+        assert!(buf.contains("if value > 10 {"));
+    }
+
+    #[test]
+    fn test_generating_binary_newtypes() {
+        let binary = |base| NewtypeImpl {
+            name: "Key".into(),
+            roots: Vec::new(),
+            base: TypeName::Primitive(base),
+            facets: Facets {
+                length: Some(16),
+                ..Facets::default()
+            },
+        };
+
+        let mut buf = String::new();
+        let mut f = RustFmt::new();
+        f.write_newtype(&mut buf, &binary(Primitive::HexBinary))
+            .expect("Writing");
+        assert!(buf.contains("let length = value.chars().count() / 2;\n"));
+        assert!(buf.contains("must have exactly 16 octets"));
+
+        buf.clear();
+        f.write_newtype(&mut buf, &binary(Primitive::Base64Binary))
+            .expect("Writing");
+        assert!(buf.contains(
+            "let length = value.chars().filter(|c| !c.is_whitespace() && *c != '=').count() * 3 / 4;\n"
+        ));
+        assert!(buf.contains("must have exactly 16 octets"));
+
+        buf.clear();
+        let mut f = RustFmt::new().map_type(
+            Primitive::Base64Binary,
+            TypeMapping::new("Bytes").import("bytes::Bytes"),
+        );
+        f.write_newtype_file(&mut buf, &binary(Primitive::Base64Binary))
+            .expect("Writing");
+        assert!(buf.starts_with("use bytes::Bytes;\n\npub struct Key(Bytes);"));
+        assert!(!buf.contains("let length"));
+    }
+
+    #[test]
+    fn test_allowing_schema_type_names() {
+        let mut f = RustFmt::new();
+        let newtype = |name: &str| NewtypeImpl {
+            name: name.into(),
+            roots: Vec::new(),
+            base: TypeName::Primitive(Primitive::String),
+            facets: Facets::default(),
+        };
+
+        let mut buf = String::new();
+        f.write_newtype(&mut buf, &newtype("guid"))
+            .expect("Writing");
+        assert!(buf.starts_with("#[allow(non_camel_case_types)]\npub struct guid(String);"));
+
+        buf.clear();
+        f.write_newtype(&mut buf, &newtype("Guid"))
+            .expect("Writing");
+        assert!(buf.starts_with("pub struct Guid(String);"));
+
+        buf.clear();
+        let animal = AbstractImpl {
+            name: "Animal".into(),
+            roots: Vec::new(),
+            subtypes: vec!["Cat".into(), "big_dog".into()],
+        };
+        f.write_abstract(&mut buf, &animal).expect("Writing");
+        assert!(buf.starts_with("#[allow(non_camel_case_types)]\npub enum Animal {"));
+    }
+
+    #[test]
+    fn test_generating_union_file() {
+        let mut buf = String::new();
+        let mut f = RustFmt::new();
+
+        let union = UnionImpl {
+            name: "SizeOrNumber".into(),
+            roots: Vec::new(),
+            members: vec![
+                TypeName::Primitive(Primitive::Int),
+                TypeName::object("Size"),
+                TypeName::Primitive(Primitive::Token),
+            ],
+        };
+
+        f.write_union_file(&mut buf, &union).expect("Writing");
+
+        assert_eq!(
+            r#"
+use super::{
+    Size,
+};
+
+pub enum SizeOrNumber {
+    Int(i32),
+    Size(Size),
+    Token(String),
+}
+
+impl std::str::FromStr for SizeOrNumber {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(value) = s.parse() {
+            return Ok(Self::Int(value));
+        }
+        if let Ok(value) = s.parse() {
+            return Ok(Self::Size(value));
+        }
+        Ok(Self::Token(s.to_string()))
+    }
+}
+"#
+            .trim(),
+            buf.split("\n\nimpl std::fmt::Display").next().unwrap()
+        );
+    }
 
     #[test]
     fn test_generating_simple_struct_file() {
         let mut buf = String::new();
-        let mut f = RustFmt;
+        let mut f = RustFmt::new();
 
         let object = ObjectImpl {
             name: "ImAStruct".into(),
+            base: None,
             fields: vec![
                 Field::new("f1", Primitive::Bool),
                 Field::new("f2", Primitive::Int),
                 Field::new("f3", Primitive::Long),
             ],
+            unordered: false,
+            roots: Vec::new(),
         };
 
         f.write_impl_file(&mut buf, &object).expect("Writing");
@@ -188,10 +4135,11 @@ impl ImAStruct {
     #[test]
     fn test_generating_struct_file() {
         let mut buf = String::new();
-        let mut f = RustFmt;
+        let mut f = RustFmt::new();
 
         let object = ObjectImpl {
             name: "ImAStruct".into(),
+            base: None,
             fields: vec![
                 Field::new("f1", Primitive::Bool),
                 Field::new("f2", Primitive::Int),
@@ -199,6 +4147,8 @@ impl ImAStruct {
                 Field::new("bool_array", TypeName::array(Primitive::Bool)),
                 Field::new("thing_array", TypeName::object("Thing")),
             ],
+            unordered: false,
+            roots: Vec::new(),
         };
 
         f.write_impl_file(&mut buf, &object).expect("Writing");
@@ -0,0 +1,1421 @@
+//! Writes the generated types as XML documents through serde.
+//!
+//! Fields renamed with a leading `@` are written as attributes and
+//! `$value` fields as the content of their element, where each variant
+//! is an element of its own. Other variants holding a value, those of
+//! abstract types, are written with an `xsi:type` attribute.
+//!
+//! Fields of elements that may be nil are read and written through the
+//! `nil` module, as elements with an `xsi:nil` attribute, and elements
+//! with a default value are read through `text_or_else`. Abstract types
+//! read the `xsi:type` attribute through `split_type`.
+
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt::{self, Write};
+use std::marker::PhantomData;
+
+/// The name of the unit struct written as an element with `xsi:nil`.
+const NIL: &str = "xsi:nil";
+
+/// A value that can't be written as XML.
+#[derive(Debug)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+fn unsupported(what: &str) -> Error {
+    Error(format!("{} can't be written as XML", what))
+}
+
+/// Writes `value` as a document with a `root` element.
+pub fn to_string<T>(value: &T, root: &str) -> Result<String, Error>
+where
+    T: Serialize + ?Sized,
+{
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>");
+    value.serialize(ElementSerializer::new(&mut out, root))?;
+    Ok(out)
+}
+
+/// Escapes text for both element content and attribute values.
+fn escape(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+/// Declares the prefix of the `xsi:type` and `xsi:nil` attributes.
+fn declare_xsi(attributes: &mut String) {
+    attributes.push_str(" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\"");
+}
+
+/// Floats as written in XSD, which spells infinity `INF`.
+fn float_text(value: f64) -> String {
+    if value.is_nan() {
+        String::from("NaN")
+    } else if value.is_infinite() {
+        String::from(if value > 0.0 { "INF" } else { "-INF" })
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes a value as an element named `name`, or as one such element
+/// for each item of a sequence.
+struct ElementSerializer<'a> {
+    out: &'a mut String,
+    name: &'a str,
+    /// Each attribute with a leading space.
+    attributes: String,
+}
+
+impl<'a> ElementSerializer<'a> {
+    fn new(out: &'a mut String, name: &'a str) -> Self {
+        Self {
+            out,
+            name,
+            attributes: String::new(),
+        }
+    }
+
+    fn write_text(self, text: &str) -> Result<(), Error> {
+        write!(self.out, "<{}{}>", self.name, self.attributes).map_err(ser::Error::custom)?;
+        escape(self.out, text);
+        write!(self.out, "</{}>", self.name).map_err(ser::Error::custom)
+    }
+}
+
+impl<'a> ser::Serializer for ElementSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = StructSerializer<'a>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.write_text(if v { "true" } else { "false" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.write_text(&float_text(f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.write_text(&float_text(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.write_text(v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), Error> {
+        Err(unsupported("Bytes"))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        write!(self.out, "<{}{}/>", self.name, self.attributes).map_err(ser::Error::custom)
+    }
+
+    fn serialize_unit_struct(mut self, name: &'static str) -> Result<(), Error> {
+        if name == NIL {
+            declare_xsi(&mut self.attributes);
+            self.attributes.push_str(" xsi:nil=\"true\"");
+        }
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.write_text(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        mut self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        declare_xsi(&mut self.attributes);
+        self.attributes.push_str(" xsi:type=\"");
+        escape(&mut self.attributes, variant);
+        self.attributes.push('"');
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqSerializer<'a>, Error> {
+        Ok(SeqSerializer {
+            out: self.out,
+            name: Some(self.name),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("Tuple structs"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("Tuple variants"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("Maps"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<StructSerializer<'a>, Error> {
+        Ok(StructSerializer {
+            out: self.out,
+            name: self.name,
+            attributes: self.attributes,
+            content: String::new(),
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported("Struct variants"))
+    }
+}
+
+/// Writes each item of a sequence as an element named `name`,
+/// or as content if there is no name.
+struct SeqSerializer<'a> {
+    out: &'a mut String,
+    name: Option<&'a str>,
+}
+
+impl<'a> ser::SerializeSeq for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self.name {
+            Some(name) => value.serialize(ElementSerializer::new(&mut *self.out, name)),
+            None => value.serialize(ContentSerializer {
+                out: &mut *self.out,
+            }),
+        }
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+// Fixed size arrays are serialized as tuples.
+impl<'a> ser::SerializeTuple for SeqSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Writes the fields of a struct as the attributes and
+/// content of an element named `name`.
+struct StructSerializer<'a> {
+    out: &'a mut String,
+    name: &'a str,
+    attributes: String,
+    content: String,
+}
+
+impl<'a> ser::SerializeStruct for StructSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        if let Some(attribute) = key.strip_prefix('@') {
+            if let Some(text) = value.serialize(TextSerializer)? {
+                write!(self.attributes, " {}=\"", attribute).map_err(ser::Error::custom)?;
+                escape(&mut self.attributes, &text);
+                self.attributes.push('"');
+            }
+            Ok(())
+        } else if key == "$value" {
+            value.serialize(ContentSerializer {
+                out: &mut self.content,
+            })
+        } else {
+            value.serialize(ElementSerializer::new(&mut self.content, key))
+        }
+    }
+
+    fn end(self) -> Result<(), Error> {
+        if self.content.is_empty() {
+            write!(self.out, "<{}{}/>", self.name, self.attributes)
+        } else {
+            write!(
+                self.out,
+                "<{}{}>{}</{}>",
+                self.name, self.attributes, self.content, self.name
+            )
+        }
+        .map_err(ser::Error::custom)
+    }
+}
+
+/// Writes a value as the content of an element. Variants holding
+/// a value are written as an element named after the variant.
+struct ContentSerializer<'a> {
+    out: &'a mut String,
+}
+
+impl<'a> ContentSerializer<'a> {
+    fn write_text(self, text: &str) -> Result<(), Error> {
+        escape(self.out, text);
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for ContentSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), Error> {
+        self.write_text(if v { "true" } else { "false" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), Error> {
+        self.write_text(&float_text(f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), Error> {
+        self.write_text(&float_text(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), Error> {
+        self.write_text(&v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), Error> {
+        self.write_text(v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<(), Error> {
+        Err(unsupported("Bytes"))
+    }
+
+    fn serialize_none(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.write_text(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(ElementSerializer::new(self.out, variant))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqSerializer<'a>, Error> {
+        Ok(SeqSerializer {
+            out: self.out,
+            name: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("Tuple structs"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("Tuple variants"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("Maps"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(unsupported("Structs without an element"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported("Struct variants"))
+    }
+}
+
+/// Writes a value as the text of an attribute, `None` leaving it out.
+struct TextSerializer;
+
+impl TextSerializer {
+    fn text(text: impl Into<String>) -> Result<Option<String>, Error> {
+        Ok(Some(text.into()))
+    }
+}
+
+impl ser::Serializer for TextSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+    type SerializeSeq = Impossible<Option<String>, Error>;
+    type SerializeTuple = Impossible<Option<String>, Error>;
+    type SerializeTupleStruct = Impossible<Option<String>, Error>;
+    type SerializeTupleVariant = Impossible<Option<String>, Error>;
+    type SerializeMap = Impossible<Option<String>, Error>;
+    type SerializeStruct = Impossible<Option<String>, Error>;
+    type SerializeStructVariant = Impossible<Option<String>, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<String>, Error> {
+        Self::text(if v { "true" } else { "false" })
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<String>, Error> {
+        Self::text(v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Option<String>, Error> {
+        Self::text(float_text(f64::from(v)))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Option<String>, Error> {
+        Self::text(float_text(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<String>, Error> {
+        Self::text(v)
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<String>, Error> {
+        Self::text(v)
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Option<String>, Error> {
+        Err(unsupported("Bytes"))
+    }
+
+    fn serialize_none(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Option<String>, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<String>, Error> {
+        Self::text("")
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<String>, Error> {
+        Self::text("")
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Option<String>, Error> {
+        Self::text(variant)
+    }
+
+    fn serialize_newtype_struct<T>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Option<String>, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Option<String>, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        Err(unsupported("Attributes holding elements"))
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(unsupported("Attributes holding several values"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(unsupported("Tuples"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(unsupported("Tuple structs"))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(unsupported("Tuple variants"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(unsupported("Maps"))
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(unsupported("Attributes holding elements"))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(unsupported("Struct variants"))
+    }
+}
+
+/// Reads an element holding text, or `default()` if it is empty, as the
+/// default value of an element only applies to empty elements.
+pub fn text_or_else<'de, T, D, F>(deserializer: D, default: F) -> Result<T, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+    F: FnOnce() -> T,
+{
+    let text = deserializer.deserialize_struct("", &["$value"], TextVisitor(PhantomData))?;
+    Ok(text.value.unwrap_or_else(default))
+}
+
+/// The text of an element, `None` if it is empty.
+struct Text<T> {
+    nil: bool,
+    value: Option<T>,
+}
+
+fn is_true(value: &str) -> bool {
+    value == "true" || value == "1"
+}
+
+/// Visits the attributes and text of an element.
+struct TextVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for TextVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Text<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an element holding text")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut text = Text {
+            nil: false,
+            value: None,
+        };
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "nil" => text.nil = is_true(&map.next_value::<String>()?),
+                "$value" => text.value = Some(map.next_value::<T>()?),
+                _ => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+            }
+        }
+
+        Ok(text)
+    }
+}
+
+/// Reads and writes `Option<T>` fields of elements that may be nil, where
+/// `None` is an element with `xsi:nil="true"`, through
+/// `#[serde(with = "xml_writer::nil")]`.
+///
+/// `nil::option` does the same for `Option<Option<T>>`, where only
+/// `Some(None)` is nil, and `nil::wrapped` for wrapper types.
+pub mod nil {
+    use serde::de::{self, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+    use serde::{forward_to_deserialize_any, Deserialize, Deserializer, Serialize, Serializer};
+    use std::{cell::Cell, fmt, marker::PhantomData};
+
+    pub fn serialize<T, S>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Serialize,
+        S: Serializer,
+    {
+        match value {
+            Some(value) => value.serialize(serializer),
+            None => serializer.serialize_unit_struct(super::NIL),
+        }
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        T: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        let element = Element {
+            deserializer: Cell::new(Some(deserializer)),
+            state: Cell::new(State::Unread),
+        };
+
+        match T::deserialize(&element) {
+            Ok(value) => Ok(Some(value)),
+            Err(_) if element.state.get() == State::Nil => Ok(None),
+            Err(error) if element.state.get() != State::Text => Err(error),
+            // The value was left unread to be read along with the attributes.
+            Err(_) => {
+                let text = element.take()?.deserialize_struct(
+                    "",
+                    &["$value"],
+                    super::TextVisitor(PhantomData),
+                )?;
+                match text.value {
+                    _ if text.nil => Ok(None),
+                    Some(value) => Ok(Some(value)),
+                    None => {
+                        let empty: de::value::StrDeserializer<D::Error> = "".into_deserializer();
+                        T::deserialize(empty).map(Some)
+                    }
+                }
+            }
+        }
+    }
+
+    /// For `Option<Option<T>>` fields, where `None` is a missing element.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<T, S>(value: &Option<Option<T>>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: Serialize,
+            S: Serializer,
+        {
+            match value {
+                Some(value) => super::serialize(value, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+        where
+            T: Deserialize<'de>,
+            D: Deserializer<'de>,
+        {
+            super::deserialize(deserializer).map(Some)
+        }
+    }
+
+    /// For wrapper types converted from `Option<T>` when read, and to
+    /// `Option<&T>` by reference when written. The functions take `T`
+    /// explicitly, as in `serialize::<String, _, _>`.
+    pub mod wrapped {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+        pub fn serialize<T, W, S>(value: &W, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: Serialize,
+            for<'a> &'a W: Into<Option<&'a T>>,
+            S: Serializer,
+        {
+            super::serialize(&value.into(), serializer)
+        }
+
+        pub fn deserialize<'de, T, W, D>(deserializer: D) -> Result<W, D::Error>
+        where
+            T: Deserialize<'de>,
+            W: From<Option<T>>,
+            D: Deserializer<'de>,
+        {
+            super::deserialize(deserializer).map(W::from)
+        }
+
+        /// For optional fields of wrapper types, where `None` is a missing element.
+        pub mod option {
+            use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+            pub fn serialize<T, W, S>(value: &Option<W>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                T: Serialize,
+                for<'a> &'a W: Into<Option<&'a T>>,
+                S: Serializer,
+            {
+                match value {
+                    Some(value) => super::serialize(value, serializer),
+                    None => serializer.serialize_none(),
+                }
+            }
+
+            pub fn deserialize<'de, T, W, D>(deserializer: D) -> Result<Option<W>, D::Error>
+            where
+                T: Deserialize<'de>,
+                W: From<Option<T>>,
+                D: Deserializer<'de>,
+            {
+                super::deserialize(deserializer).map(Some)
+            }
+        }
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unread,
+        /// The value is text, read from the `$value` of the element.
+        Text,
+        Nil,
+    }
+
+    /// An element read once, as a struct if the value is one.
+    /// Other values are left to be read as text.
+    struct Element<D> {
+        deserializer: Cell<Option<D>>,
+        state: Cell<State>,
+    }
+
+    impl<D> Element<D> {
+        fn take<E: de::Error>(&self) -> Result<D, E> {
+            self.deserializer
+                .take()
+                .ok_or_else(|| de::Error::custom("the element was read twice"))
+        }
+
+        fn struct_visitor<V>(&self, visitor: V) -> StructVisitor<'_, V> {
+            StructVisitor {
+                visitor,
+                state: &self.state,
+            }
+        }
+    }
+
+    fn unless_nil<T, E: de::Error>(value: Option<T>) -> Result<T, E> {
+        value.ok_or_else(|| de::Error::custom("the element is nil"))
+    }
+
+    impl<'de, D> Deserializer<'de> for &Element<D>
+    where
+        D: Deserializer<'de>,
+    {
+        type Error = D::Error;
+
+        fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            self.state.set(State::Text);
+            Err(de::Error::custom("the value is read as text"))
+        }
+
+        fn deserialize_struct<V>(
+            self,
+            name: &'static str,
+            fields: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            let visitor = self.struct_visitor(visitor);
+            unless_nil(self.take()?.deserialize_struct(name, fields, visitor)?)
+        }
+
+        fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, D::Error>
+        where
+            V: Visitor<'de>,
+        {
+            let visitor = self.struct_visitor(visitor);
+            unless_nil(self.take()?.deserialize_map(visitor)?)
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct enum identifier ignored_any
+        }
+    }
+
+    /// Visits a struct, or nothing if it is nil.
+    struct StructVisitor<'a, V> {
+        visitor: V,
+        state: &'a Cell<State>,
+    }
+
+    impl<'a, 'de, V> Visitor<'de> for StructVisitor<'a, V>
+    where
+        V: Visitor<'de>,
+    {
+        type Value = Option<V::Value>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.visitor.expecting(f)
+        }
+
+        fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let value = self.visitor.visit_map(WithoutNil {
+                map: &mut map,
+                state: self.state,
+            });
+            if self.state.get() != State::Nil {
+                return value.map(Some);
+            }
+
+            while map.next_key::<de::IgnoredAny>()?.is_some() {
+                map.next_value::<de::IgnoredAny>()?;
+            }
+            Ok(None)
+        }
+    }
+
+    /// The attributes and content of an element, but `xsi:nil`.
+    struct WithoutNil<'a, A> {
+        map: A,
+        state: &'a Cell<State>,
+    }
+
+    impl<'a, 'de, A> MapAccess<'de> for WithoutNil<'a, A>
+    where
+        A: MapAccess<'de>,
+    {
+        type Error = A::Error;
+
+        fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+        where
+            K: DeserializeSeed<'de>,
+        {
+            while let Some(key) = self.map.next_key::<String>()? {
+                if key != "nil" {
+                    let key: de::value::StringDeserializer<A::Error> = key.into_deserializer();
+                    return seed.deserialize(key).map(Some);
+                }
+                if super::is_true(&self.map.next_value::<String>()?) {
+                    self.state.set(State::Nil);
+                }
+            }
+            Ok(None)
+        }
+
+        fn next_value_seed<S>(&mut self, seed: S) -> Result<S::Value, A::Error>
+        where
+            S: DeserializeSeed<'de>,
+        {
+            self.map.next_value_seed(seed)
+        }
+    }
+}
+
+/// Reads the `xsi:type` attribute of an element of an abstract type,
+/// which serde-xml-rs reads as `type`. Returns the local name of the type
+/// and the rest of the element for the subtype to read, including the
+/// attributes read before `xsi:type`.
+pub fn split_type<'de, A>(
+    mut map: A,
+) -> Result<(String, de::value::MapAccessDeserializer<Attributes<A>>), A::Error>
+where
+    A: MapAccess<'de>,
+{
+    // serde-xml-rs reads attributes before any content.
+    let mut attributes = Vec::new();
+    let subtype = loop {
+        match map.next_key::<String>()? {
+            Some(key) if key == "type" => break map.next_value::<String>()?,
+            Some(key) => {
+                let value = map.next_value::<String>()?;
+                attributes.push((key, value));
+            }
+            None => return Err(de::Error::missing_field("xsi:type")),
+        }
+    };
+
+    let local = subtype.rsplit(':').next().unwrap_or_default().to_string();
+    let rest = Attributes {
+        attributes: attributes.into_iter(),
+        value: None,
+        map,
+    };
+    Ok((local, de::value::MapAccessDeserializer::new(rest)))
+}
+
+/// The attributes read before `xsi:type`, followed by the rest of the element.
+pub struct Attributes<A> {
+    attributes: std::vec::IntoIter<(String, String)>,
+    value: Option<String>,
+    map: A,
+}
+
+impl<'de, A> MapAccess<'de> for Attributes<A>
+where
+    A: MapAccess<'de>,
+{
+    type Error = A::Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, A::Error>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.attributes.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                let key: de::value::StringDeserializer<A::Error> = key.into_deserializer();
+                seed.deserialize(key).map(Some)
+            }
+            None => self.map.next_key_seed(seed),
+        }
+    }
+
+    fn next_value_seed<S>(&mut self, seed: S) -> Result<S::Value, A::Error>
+    where
+        S: DeserializeSeed<'de>,
+    {
+        match self.value.take() {
+            Some(value) => seed.deserialize(AttributeValue(value, PhantomData)),
+            None => self.map.next_value_seed(seed),
+        }
+    }
+}
+
+/// The value of an attribute, parsed as the type read from it.
+struct AttributeValue<E>(String, PhantomData<E>);
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, E>
+            where
+                V: Visitor<'de>,
+            {
+                match self.0.trim().parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(de::Unexpected::Str(&self.0), &visitor)),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de, E> Deserializer<'de> for AttributeValue<E>
+where
+    E: de::Error,
+{
+    type Error = E;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_string(self.0)
+    }
+
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0.trim() {
+            "true" | "1" => visitor.visit_bool(true),
+            "false" | "0" => visitor.visit_bool(false),
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Str(&self.0),
+                &visitor,
+            )),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, E>
+    where
+        V: Visitor<'de>,
+    {
+        let value: de::value::StringDeserializer<E> = self.0.into_deserializer();
+        visitor.visit_enum(value)
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::{Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize)]
+    struct Note {
+        #[serde(rename = "Text")]
+        text: String,
+        #[serde(rename = "@Lang")]
+        lang: &'static str,
+        #[serde(rename = "@Id", skip_serializing_if = "Option::is_none")]
+        id: Option<u32>,
+        #[serde(rename = "Author")]
+        author: Option<&'static str>,
+    }
+
+    #[derive(Serialize)]
+    struct Order {
+        #[serde(rename = "Line")]
+        lines: Vec<Line>,
+        #[serde(rename = "@Number")]
+        number: u32,
+    }
+
+    #[derive(Serialize)]
+    struct Line {
+        #[serde(rename = "Tag")]
+        tags: Vec<&'static str>,
+        #[serde(rename = "Size")]
+        sizes: [f64; 2],
+    }
+
+    /// Written by `serialize_bytes`, as no type derives it.
+    struct Bytes;
+
+    impl Serialize for Bytes {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(b"bytes")
+        }
+    }
+
+    #[test]
+    fn write_escaped_text() {
+        #[derive(Serialize)]
+        struct Quote {
+            #[serde(rename = "@By")]
+            by: &'static str,
+            #[serde(rename = "$value")]
+            text: &'static str,
+        }
+
+        let quote = Quote {
+            by: r#"<"Tom" & 'Jerry'>"#,
+            text: r#"1 < 2 & "3" > '0'"#,
+        };
+        assert_eq!(
+            to_string(&quote, "Quote").expect("Writing"),
+            r#"<?xml version="1.0" encoding="UTF-8"?><Quote By="&lt;&quot;Tom&quot; &amp; 'Jerry'&gt;">1 &lt; 2 &amp; &quot;3&quot; &gt; '0'</Quote>"#
+        );
+    }
+
+    #[test]
+    fn write_attributes_before_content() {
+        let note = Note {
+            text: String::from("Hi"),
+            lang: "en",
+            id: Some(7),
+            author: Some("Ann"),
+        };
+        assert_eq!(
+            to_string(&note, "Note").expect("Writing"),
+            r#"<?xml version="1.0" encoding="UTF-8"?><Note Lang="en" Id="7"><Text>Hi</Text><Author>Ann</Author></Note>"#
+        );
+    }
+
+    #[test]
+    fn leave_out_missing_values() {
+        let note = Note {
+            text: String::new(),
+            lang: "en",
+            id: None,
+            author: None,
+        };
+        assert_eq!(
+            to_string(&note, "Note").expect("Writing"),
+            r#"<?xml version="1.0" encoding="UTF-8"?><Note Lang="en"><Text></Text></Note>"#
+        );
+    }
+
+    #[test]
+    fn write_nested_sequences() {
+        let order = Order {
+            lines: vec![
+                Line {
+                    tags: vec!["a", "b"],
+                    sizes: [1.5, f64::INFINITY],
+                },
+                Line {
+                    tags: Vec::new(),
+                    sizes: [f64::NAN, -2.0],
+                },
+            ],
+            number: 1,
+        };
+        assert_eq!(
+            to_string(&order, "Order").expect("Writing"),
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?><Order Number="1">"#,
+                "<Line><Tag>a</Tag><Tag>b</Tag><Size>1.5</Size><Size>INF</Size></Line>",
+                "<Line><Size>NaN</Size><Size>-2</Size></Line>",
+                "</Order>"
+            )
+        );
+    }
+
+    #[test]
+    fn fail_on_unsupported_values() {
+        #[derive(Serialize)]
+        struct Pair(u8, u8);
+
+        #[derive(Serialize)]
+        enum Shape {
+            Point { x: u8 },
+            Line(u8, u8),
+        }
+
+        #[derive(Serialize)]
+        struct Attribute<T> {
+            #[serde(rename = "@Value")]
+            value: T,
+        }
+
+        #[derive(Serialize)]
+        struct Content<T> {
+            #[serde(rename = "$value")]
+            value: T,
+        }
+
+        let error = |result: Result<String, Error>| result.expect_err("Failing").to_string();
+
+        assert_eq!(
+            error(to_string(&Pair(1, 2), "Pair")),
+            "Tuple structs can't be written as XML"
+        );
+        assert_eq!(
+            error(to_string(&Shape::Point { x: 1 }, "Shape")),
+            "Struct variants can't be written as XML"
+        );
+        assert_eq!(
+            error(to_string(&Shape::Line(1, 2), "Shape")),
+            "Tuple variants can't be written as XML"
+        );
+        assert_eq!(
+            error(to_string(&BTreeMap::from([(1, 2)]), "Map")),
+            "Maps can't be written as XML"
+        );
+        assert_eq!(
+            error(to_string(&Bytes, "Bytes")),
+            "Bytes can't be written as XML"
+        );
+        assert_eq!(
+            error(to_string(&Attribute { value: vec![1, 2] }, "A")),
+            "Attributes holding several values can't be written as XML"
+        );
+        assert_eq!(
+            error(to_string(
+                &Attribute {
+                    value: Attribute { value: 1 }
+                },
+                "A"
+            )),
+            "Attributes holding elements can't be written as XML"
+        );
+        assert_eq!(
+            error(to_string(
+                &Content {
+                    value: Attribute { value: 1 }
+                },
+                "C"
+            )),
+            "Structs without an element can't be written as XML"
+        );
+    }
+}
@@ -0,0 +1,295 @@
+use super::{
+    All, Choice, ComplexType, Element, Extension, Group, Kind, Particle, Schema, Sequence,
+    SimpleType,
+};
+use crate::Context;
+use heck::CamelCase;
+use std::mem;
+
+impl Schema {
+    /// Moves the types defined inline in elements to the top level,
+    /// and makes the elements refer to them by name.
+    ///
+    /// Each type is named after its parent type and element, such as
+    /// `OrderLine`, or after the element alone for top level elements.
+    /// Names already taken get a numeric suffix.
+    pub fn hoist_anonymous_types(&mut self, ctx: &mut Context) {
+        let mut hoisted = Hoisted::default();
+
+        for el in &mut self.elements {
+            hoisted.hoist(el, "", ctx);
+        }
+
+        for ct in &mut self.complex_types {
+            let parent = ct.name.clone();
+            ct.visit_elements(&mut |el| hoisted.hoist(el, &parent, ctx));
+        }
+
+        for group in &mut self.groups {
+            let parent = group.name.clone().unwrap_or_default();
+            group.visit_elements(&mut |el| hoisted.hoist(el, &parent, ctx));
+        }
+
+        // Hoisted types may define types inline themselves.
+        while !hoisted.complex_types.is_empty() {
+            for mut ct in mem::take(&mut hoisted.complex_types) {
+                let parent = ct.name.clone();
+                ct.visit_elements(&mut |el| hoisted.hoist(el, &parent, ctx));
+                self.complex_types.push(ct);
+            }
+        }
+
+        self.simple_types.extend(hoisted.simple_types);
+
+        // Lists and unions may define their item and member types inline,
+        // which may be lists or unions themselves.
+        let mut i = 0;
+        while i < self.simple_types.len() {
+            let inner = self.simple_types[i].hoist_inline_types(ctx);
+            self.simple_types.extend(inner);
+            i += 1;
+        }
+    }
+}
+
+impl SimpleType {
+    /// Takes the item type of a list and the member types of a union
+    /// that are defined inline, named after this type such as `SizesItem`.
+    fn hoist_inline_types(&mut self, ctx: &mut Context) -> Vec<SimpleType> {
+        let mut hoisted = Vec::new();
+
+        if let Some(list) = &mut self.list {
+            if let Some(mut st) = list.simple_type.take() {
+                st.name = ctx.unique_name(&format!("{}Item", self.name));
+                list.item_type = Some(Kind::Object(st.expanded_name()));
+                hoisted.push(*st);
+            }
+        }
+
+        if let Some(union) = &mut self.union {
+            for mut st in union.simple_types.drain(..) {
+                st.name = ctx.unique_name(&format!("{}Member", self.name));
+                union.member_types.push(Kind::Object(st.expanded_name()));
+                hoisted.push(st);
+            }
+        }
+
+        hoisted
+    }
+}
+
+#[derive(Default)]
+struct Hoisted {
+    complex_types: Vec<ComplexType>,
+    simple_types: Vec<SimpleType>,
+}
+
+impl Hoisted {
+    /// Takes the inline type of `el`, if it has one and no `type`.
+    fn hoist(&mut self, el: &mut Element, parent: &str, ctx: &mut Context) {
+        if el.kind.is_some() || (el.complex_type.is_none() && el.simple_type.is_none()) {
+            return;
+        }
+
+        let name = ctx.unique_name(&format!("{}{}", parent, el.name.to_camel_case()));
+
+        if let Some(mut ct) = el.complex_type.take() {
+            ct.name = name;
+            el.kind = Some(Kind::Object(ct.expanded_name()));
+            self.complex_types.push(*ct);
+        } else if let Some(mut st) = el.simple_type.take() {
+            st.name = name;
+            el.kind = Some(Kind::Object(st.expanded_name()));
+            self.simple_types.push(*st);
+        }
+    }
+}
+
+/// Calls `f` with every element of the content, however deeply nested.
+pub(super) trait VisitElements {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element));
+}
+
+impl<T: VisitElements> VisitElements for Vec<T> {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        self.iter_mut().for_each(|item| item.visit_elements(f));
+    }
+}
+
+impl VisitElements for Element {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        f(self)
+    }
+}
+
+impl VisitElements for ComplexType {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        self.sequences.visit_elements(f);
+        self.alls.visit_elements(f);
+        self.choices.visit_elements(f);
+        for cc in &mut self.complex_contents {
+            cc.extensions.visit_elements(f);
+        }
+    }
+}
+
+impl VisitElements for Extension {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        self.sequences.visit_elements(f);
+        self.alls.visit_elements(f);
+        self.choices.visit_elements(f);
+    }
+}
+
+impl VisitElements for Group {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        self.sequences.visit_elements(f);
+        self.alls.visit_elements(f);
+        self.choices.visit_elements(f);
+    }
+}
+
+impl VisitElements for Sequence {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        self.particles.visit_elements(f);
+    }
+}
+
+impl VisitElements for Particle {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        match self {
+            Particle::Element(el) => el.visit_elements(f),
+            Particle::Choice(choice) => choice.visit_elements(f),
+            Particle::Group(_) => (),
+            Particle::Sequence(seq) => seq.visit_elements(f),
+        }
+    }
+}
+
+impl VisitElements for Choice {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        self.elements.visit_elements(f);
+        self.choices.visit_elements(f);
+        self.sequences.visit_elements(f);
+    }
+}
+
+impl VisitElements for All {
+    fn visit_elements(&mut self, f: &mut dyn FnMut(&mut Element)) {
+        self.elements.visit_elements(f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::ExpandedName;
+
+    #[test]
+    fn hoist_anonymous_types() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="order">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="Line" maxOccurs="unbounded">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="Quantity">
+                <xs:simpleType>
+                  <xs:restriction base="xs:int" />
+                </xs:simpleType>
+              </xs:element>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:element>
+      </xs:sequence>
+    </xs:complexType>
+  </xs:element>
+  <xs:complexType name="OrderLine">
+    <xs:sequence>
+      <xs:element name="Id" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let mut schema: Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let mut ctx = Context::new(schema.complex_types.iter().map(|ct| ct.name.clone()));
+        schema.hoist_anonymous_types(&mut ctx);
+
+        assert_eq!(
+            schema.elements[0].kind,
+            Some(Kind::Object(ExpandedName::new(None, "Order")))
+        );
+        assert_eq!(schema.elements[0].complex_type, None);
+        assert_eq!(
+            schema
+                .complex_types
+                .iter()
+                .map(|ct| ct.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["OrderLine", "Order", "OrderLine2"]
+        );
+        assert_eq!(
+            schema.complex_types[1].sequences[0]
+                .elements()
+                .next()
+                .unwrap()
+                .kind,
+            Some(Kind::Object(ExpandedName::new(None, "OrderLine2")))
+        );
+        assert_eq!(schema.simple_types[0].name, "OrderLine2Quantity");
+    }
+
+    #[test]
+    fn hoist_inline_item_and_member_types() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:element name="Sizes">
+    <xs:simpleType>
+      <xs:list>
+        <xs:simpleType>
+          <xs:union memberTypes="xs:int">
+            <xs:simpleType>
+              <xs:restriction base="xs:string" />
+            </xs:simpleType>
+          </xs:union>
+        </xs:simpleType>
+      </xs:list>
+    </xs:simpleType>
+  </xs:element>
+</xs:schema>
+"#;
+
+        let mut schema: Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let mut ctx = Context::new(Vec::new());
+        schema.hoist_anonymous_types(&mut ctx);
+
+        assert_eq!(
+            schema
+                .simple_types
+                .iter()
+                .map(|st| st.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Sizes", "SizesItem", "SizesItemMember"]
+        );
+
+        let list = schema.simple_types[0].list.as_ref().expect("List");
+        assert_eq!(list.simple_type, None);
+        assert_eq!(
+            list.item_type,
+            Some(Kind::Object(ExpandedName::new(None, "SizesItem")))
+        );
+
+        let union = schema.simple_types[1].union.as_ref().expect("Union");
+        assert!(union.simple_types.is_empty());
+        assert_eq!(
+            union.member_types,
+            vec![
+                Kind::Int,
+                Kind::Object(ExpandedName::new(None, "SizesItemMember"))
+            ]
+        );
+    }
+}
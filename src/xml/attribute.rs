@@ -0,0 +1,165 @@
+use super::Kind;
+use crate::{Diagnostic, Field, Primitive, Report, TypeName};
+
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+pub struct Attribute {
+    /// Missing when the attribute refers to another with `ref`.
+    pub name: Option<String>,
+
+    /// A top level attribute this one stands for.
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+
+    /// Missing means `xs:anySimpleType`.
+    #[serde(rename = "type")]
+    pub kind: Option<Kind>,
+
+    #[serde(rename = "use", default)]
+    pub usage: AttributeUse,
+
+    pub default: Option<String>,
+    pub fixed: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AttributeUse {
+    #[default]
+    Optional,
+    Required,
+    Prohibited,
+}
+
+impl Attribute {
+    /// Creates the field of an attribute in `parent`.
+    /// Prohibited attributes have no field.
+    pub fn into_field(self, parent: &str, report: &mut Report) -> Option<Field> {
+        let name = match self.name {
+            Some(name) => name,
+            None => {
+                report
+                    .push(Diagnostic::warning("Dropping attribute without a name").in_type(parent));
+                return None;
+            }
+        };
+
+        if self.usage == AttributeUse::Prohibited {
+            return None;
+        }
+
+        let type_name = self
+            .kind
+            .map(TypeName::from)
+            .unwrap_or(TypeName::Primitive(Primitive::AnySimpleType));
+
+        let mut field = Field::new(name, type_name).attribute();
+        field.required = self.usage == AttributeUse::Required;
+        field.default = self.default;
+        field.fixed = self.fixed;
+
+        Some(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Context, FieldKind};
+
+    #[test]
+    fn parse_attributes() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Amount">
+    <xs:sequence>
+      <xs:element minOccurs="1" maxOccurs="1" name="Value" type="xs:double" />
+    </xs:sequence>
+    <xs:attribute name="Currency" type="xs:string" use="required" />
+    <xs:attribute name="Rate" type="xs:double" default="1" />
+    <xs:attribute name="Version" fixed="2" />
+    <xs:attribute name="Legacy" type="xs:string" use="prohibited" />
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let schema: crate::xml::Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let attributes = &schema.complex_types[0].attributes;
+
+        assert_eq!(attributes.len(), 4);
+        assert_eq!(attributes[0].name.as_deref(), Some("Currency"));
+        assert_eq!(attributes[0].kind, Some(Kind::String));
+        assert_eq!(attributes[0].usage, AttributeUse::Required);
+        assert_eq!(attributes[1].usage, AttributeUse::Optional);
+        assert_eq!(attributes[1].default.as_deref(), Some("1"));
+        assert_eq!(attributes[2].kind, None);
+        assert_eq!(attributes[2].fixed.as_deref(), Some("2"));
+        assert_eq!(attributes[3].usage, AttributeUse::Prohibited);
+
+        let mut ctx = Context::default();
+        let object = schema
+            .complex_types
+            .into_iter()
+            .next()
+            .unwrap()
+            .into_object_impl(&mut ctx)
+            .expect("Creating ObjectImpl");
+
+        assert!(ctx.report.is_empty());
+        assert_eq!(
+            object.fields,
+            vec![
+                Field::new("Value", Primitive::Double),
+                Field::new("Currency", Primitive::String).attribute(),
+                Field::new("Rate", Primitive::Double)
+                    .attribute()
+                    .optional()
+                    .with_default("1"),
+                Field::new("Version", Primitive::AnySimpleType)
+                    .attribute()
+                    .optional()
+                    .with_fixed("2"),
+            ]
+        );
+        assert_eq!(object.fields[1].kind, FieldKind::Attribute);
+    }
+
+    #[test]
+    fn resolve_attribute_references() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:attribute name="Lang" type="xs:language" default="sv" />
+  <xs:complexType name="Note">
+    <xs:sequence>
+      <xs:element name="Text" type="xs:string" />
+    </xs:sequence>
+    <xs:attribute ref="Lang" use="required" />
+    <xs:attribute ref="xml:space" />
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let schema: crate::xml::Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let (models, report) =
+            crate::ModelSchema::create_from_xml_with_report(schema, false).expect("Creating model");
+
+        match &models.implementations[0] {
+            crate::Implementation::Object(object) => assert_eq!(
+                object.fields,
+                vec![
+                    Field::new("Text", Primitive::String),
+                    Field::new("Lang", Primitive::Language)
+                        .attribute()
+                        .with_default("sv"),
+                ]
+            ),
+            _ => panic!("Expected an ObjectImpl"),
+        }
+
+        assert_eq!(report.diagnostics.len(), 1);
+        assert_eq!(
+            report.diagnostics[0].message,
+            "Unresolved attribute `xml:space`"
+        );
+        assert_eq!(report.diagnostics[0].type_name.as_deref(), Some("Note"));
+    }
+}
@@ -0,0 +1,175 @@
+use super::{element::default_min_occurs, Element, Group, Occurence, Sequence};
+use crate::{Bounds, ChoiceImpl, Context, Diagnostic, Field, Implementation, TypeName};
+
+/// Content that is exactly one of several alternatives.
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Choice {
+    #[serde(default = "default_min_occurs")]
+    pub min_occurs: i8,
+    #[serde(default)]
+    pub max_occurs: Occurence,
+
+    #[serde(rename = "element", default)]
+    pub elements: Vec<Element>,
+
+    /// Nested choices, their alternatives are alternatives of this choice.
+    #[serde(rename = "choice", default)]
+    pub choices: Vec<Choice>,
+
+    #[serde(rename = "sequence", default)]
+    pub sequences: Vec<Sequence>,
+
+    /// Referenced groups, their choices are alternatives of this choice.
+    #[serde(rename = "group", default)]
+    pub groups: Vec<Group>,
+}
+
+impl Choice {
+    /// Creates the field holding the choice in `parent`.
+    /// The ChoiceImpl of the field is hoisted into `ctx`.
+    pub fn into_field(self, parent: &str, ctx: &mut Context) -> Option<Field> {
+        let name = ctx.unique_name(&format!("{}Choice", parent));
+        let field_name = name.trim_start_matches(parent).to_string();
+
+        let required = self.min_occurs != 0;
+        let bounds = if self.max_occurs.is_many() {
+            Some(Bounds {
+                min: self.min_occurs.max(0) as u64,
+                max: match self.max_occurs {
+                    Occurence::Bound(n) => Some(n as u64),
+                    Occurence::Unbounded => None,
+                },
+            })
+        } else {
+            None
+        };
+
+        let mut elements = Vec::new();
+        self.flatten_into(&mut elements, parent, ctx);
+
+        let variants = elements
+            .into_iter()
+            .filter_map(|el| el.into_field(&name, &mut ctx.report))
+            .collect::<Vec<_>>();
+
+        if variants.is_empty() {
+            ctx.report.push(
+                Diagnostic::warning("Dropping choice without alternatives")
+                    .in_type(parent)
+                    .in_field(field_name),
+            );
+            return None;
+        }
+
+        ctx.hoisted.push(Implementation::Choice(ChoiceImpl {
+            name: name.clone(),
+            variants,
+        }));
+
+        let type_name = match bounds {
+            Some(_) => TypeName::array(TypeName::object(name)),
+            None => TypeName::object(name),
+        };
+
+        let mut field = Field::new(field_name, type_name).choice();
+        field.required = required;
+        field.bounds = bounds;
+
+        Some(field)
+    }
+
+    /// Collects the elements of this and all nested or referenced choices.
+    /// Sequences as alternatives are not supported and dropped.
+    fn flatten_into(self, elements: &mut Vec<Element>, parent: &str, ctx: &mut Context) {
+        elements.extend(self.elements);
+
+        if !self.sequences.is_empty() {
+            ctx.report.push(
+                Diagnostic::warning("Dropping sequence alternatives of choice").in_type(parent),
+            );
+        }
+
+        for choice in self.choices {
+            choice.flatten_into(elements, parent, ctx);
+        }
+
+        for group in self.groups {
+            let group = match ctx.enter_group(&group, parent) {
+                Some(group) => group,
+                None => continue,
+            };
+
+            if !group.sequences.is_empty() || !group.alls.is_empty() {
+                ctx.report.push(
+                    Diagnostic::warning("Dropping sequence alternatives of choice").in_type(parent),
+                );
+            }
+
+            for choice in group.choices {
+                choice.flatten_into(elements, parent, ctx);
+            }
+
+            ctx.leave_group();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FieldKind, Primitive};
+
+    #[test]
+    fn choice_into_field() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Payment">
+    <xs:sequence>
+      <xs:element minOccurs="1" maxOccurs="1" name="Amount" type="xs:double" />
+      <xs:choice minOccurs="0">
+        <xs:element name="Iban" type="xs:string" />
+        <xs:choice>
+          <xs:element name="Bankgiro" type="xs:string" />
+          <xs:element name="Plusgiro" type="xs:string" />
+        </xs:choice>
+      </xs:choice>
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let schema: crate::xml::Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let mut ctx = Context::new(vec![String::from("Payment")]);
+        let object = schema
+            .complex_types
+            .into_iter()
+            .next()
+            .unwrap()
+            .into_object_impl(&mut ctx)
+            .expect("Creating ObjectImpl");
+
+        assert!(ctx.report.is_empty());
+        assert_eq!(
+            object.fields,
+            vec![
+                Field::new("Amount", Primitive::Double),
+                Field::new("Choice", TypeName::object("PaymentChoice"))
+                    .choice()
+                    .optional(),
+            ]
+        );
+        assert_eq!(object.fields[1].kind, FieldKind::Choice);
+        assert_eq!(
+            ctx.hoisted,
+            vec![Implementation::Choice(ChoiceImpl {
+                name: "PaymentChoice".into(),
+                variants: vec![
+                    Field::new("Iban", Primitive::String),
+                    Field::new("Bankgiro", Primitive::String),
+                    Field::new("Plusgiro", Primitive::String),
+                ],
+            })]
+        );
+    }
+}
@@ -1,59 +1,350 @@
 use crate::{
-    xml::{ComplexContent, Element, Sequence},
-    Field, ObjectImpl, TypeName,
+    xml::{
+        All, Attribute, AttributeGroup, Choice, ComplexContent, Element, ExpandedName, Extension,
+        Group, Occurence, Particle, Sequence,
+    },
+    Bounds, Context, Diagnostic, Field, Implementation, ObjectImpl, TypeName,
 };
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use heck::CamelCase;
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
 pub struct ComplexType {
+    /// Empty for types defined inline in an element, until they are hoisted.
+    #[serde(default)]
     pub name: String,
 
+    /// The target namespace of the schema defining the type.
+    #[serde(skip, default = "crate::xml::target_namespace")]
+    pub namespace: Option<String>,
+
     #[serde(default)]
     pub r#abstract: bool,
 
     #[serde(rename = "sequence", default)]
     pub sequences: Vec<Sequence>,
 
-    /// This is just bs data, can usually be ignored.
+    #[serde(rename = "all", default)]
+    pub alls: Vec<All>,
+
+    #[serde(rename = "choice", default)]
+    pub choices: Vec<Choice>,
+
+    #[serde(rename = "group", default)]
+    pub groups: Vec<Group>,
+
+    #[serde(rename = "attribute", default)]
+    pub attributes: Vec<Attribute>,
+
+    #[serde(rename = "attributeGroup", default)]
+    pub attribute_groups: Vec<AttributeGroup>,
+
+    /// Holds the extension of a base type, if any.
     #[serde(rename = "complexContent", default)]
     pub complex_contents: Vec<ComplexContent>,
 }
 
 impl ComplexType {
-    pub fn into_object_impl(self) -> Result<ObjectImpl> {
-        if self.sequences.is_empty() {
-            anyhow::bail!("`{}` has no sequences. Cannot create ObjectImpl", self.name);
+    /// The name of the type along with its namespace.
+    pub fn expanded_name(&self) -> ExpandedName {
+        ExpandedName::new(self.namespace.as_deref(), self.name.clone())
+    }
+
+    /// The extension of a base type this type is derived from.
+    pub fn extension(&self) -> Option<&Extension> {
+        self.complex_contents
+            .iter()
+            .flat_map(|cc| cc.extensions.iter())
+            .next()
+    }
+
+    /// The item element of a type only wrapping a list of elements,
+    /// such as `ArrayOfString`, which has a single unbounded element.
+    pub fn array_item(&self) -> Option<&Element> {
+        if self.r#abstract
+            || self.extension().is_some()
+            || !self.attributes.is_empty()
+            || !self.choices.is_empty()
+            || !self.alls.is_empty()
+            || !self.groups.is_empty()
+            || !self.attribute_groups.is_empty()
+            || self.sequences.len() != 1
+        {
+            return None;
+        }
+
+        match self.sequences[0].particles.as_slice() {
+            [Particle::Element(el)]
+                if el.kind.is_some() && el.max_occurs == Occurence::Unbounded =>
+            {
+                Some(el)
+            }
+            _ => None,
+        }
+    }
+
+    /// True if the type declares no content or base type at all.
+    pub fn is_empty(&self) -> bool {
+        self.sequences.is_empty()
+            && self.alls.is_empty()
+            && self.choices.is_empty()
+            && self.groups.is_empty()
+            && self.extension().is_none()
+            && self.attributes.is_empty()
+            && self.attribute_groups.is_empty()
+    }
+
+    /// Creates an ObjectImpl from the type's own fields.
+    /// Fields inherited from the base type are not included.
+    /// Referenced groups are inlined, and choices become fields typed
+    /// as a ChoiceImpl, hoisted into `ctx`.
+    pub fn into_object_impl(self, ctx: &mut Context) -> Result<ObjectImpl> {
+        if self.is_empty() {
+            anyhow::bail!(
+                "`{}` has no content, attributes or extension. Cannot create ObjectImpl",
+                self.name
+            );
         }
 
         let name = self.name;
-        let (has_types, lacks_types): (Vec<_>, Vec<_>) = self
-            .sequences
+        let mut attributes = self.attributes;
+        let mut attribute_groups = self.attribute_groups;
+        let mut sequences = self.sequences;
+        let mut alls = self.alls;
+        let mut choices = self.choices;
+        let mut groups = self.groups;
+
+        // Content declared next to the extension is kept after the
+        // content of the extension itself.
+        let base = match self
+            .complex_contents
             .into_iter()
+            .flat_map(|cc| cc.extensions.into_iter())
             .next()
-            .ok_or_else(|| anyhow!("`{}` has no sequences. Cannot create ObjectImpl", &name))?
-            .elements
+        {
+            Some(ext) => {
+                let base = ext.base_name().map(String::from);
+                attributes.extend(ext.attributes);
+                attribute_groups.extend(ext.attribute_groups);
+                sequences.splice(0..0, ext.sequences);
+                alls.splice(0..0, ext.alls);
+                choices.splice(0..0, ext.choices);
+                groups.splice(0..0, ext.groups);
+                base
+            }
+            None => None,
+        };
+
+        let mut fields = Fields::new(&name, ctx);
+        sequences
+            .into_iter()
+            .for_each(|seq| fields.push_sequence(seq));
+        alls.into_iter().for_each(|all| fields.push_all(all));
+        choices
             .into_iter()
-            .partition(|el| el.kind.is_some());
+            .for_each(|choice| fields.push_choice(choice));
+        groups
+            .into_iter()
+            .for_each(|group| fields.push_group(group));
+        attributes
+            .into_iter()
+            .for_each(|attr| fields.push_attribute(attr));
+        attribute_groups
+            .into_iter()
+            .for_each(|group| fields.push_attribute_group(group));
 
-        lacks_types
-            .iter()
-            .for_each(|el| eprintln!("Dropping typeless field `{}` in `{}`", el.name, &name));
+        // A type has either a sequence or an all, never both.
+        let unordered = fields.alls && !fields.sequences;
+        let fields = fields.fields;
+
+        let res = ObjectImpl {
+            name,
+            base,
+            fields,
+            unordered,
+            roots: Vec::new(),
+        };
+
+        Ok(res)
+    }
+}
+
+/// Collects the fields of a type, inlining referenced groups.
+struct Fields<'a> {
+    parent: &'a str,
+    ctx: &'a mut Context,
+    fields: Vec<Field>,
+    /// If any sequence or all was found, directly or in a group.
+    sequences: bool,
+    alls: bool,
+}
+
+impl<'a> Fields<'a> {
+    fn new(parent: &'a str, ctx: &'a mut Context) -> Self {
+        Self {
+            parent,
+            ctx,
+            fields: Vec::new(),
+            sequences: false,
+            alls: false,
+        }
+    }
 
-        let fields = has_types
+    fn push_element(&mut self, el: Element) {
+        self.fields
+            .extend(el.into_field(self.parent, &mut self.ctx.report));
+    }
+
+    /// Pushes the particles of a sequence in the order they are declared,
+    /// which is the order the elements must be written in.
+    fn push_sequence(&mut self, seq: Sequence) {
+        self.sequences = true;
+        for particle in seq.particles {
+            match particle {
+                Particle::Element(el) => self.push_element(el),
+                Particle::Choice(choice) => self.push_choice(choice),
+                Particle::Group(group) => self.push_group(group),
+                Particle::Sequence(seq) => self.push_sequence(seq),
+            }
+        }
+    }
+
+    fn push_all(&mut self, all: All) {
+        self.alls = true;
+        all.elements
             .into_iter()
-            .map(|el: Element| {
-                let required = !el.is_optional();
-                let name = el.name;
-                Field {
-                    name,
-                    required,
-                    type_name: TypeName::from(el.kind.unwrap()),
-                }
-            })
-            .collect();
+            .for_each(|el| self.push_element(el));
+    }
 
-        let res = ObjectImpl { name, fields };
+    fn push_choice(&mut self, choice: Choice) {
+        self.fields.extend(choice.into_field(self.parent, self.ctx));
+    }
 
-        Ok(res)
+    /// Inlines the fields of a referenced group.
+    /// All of them are optional if the group is.
+    fn push_group(&mut self, reference: Group) {
+        let group = match self.ctx.enter_group(&reference, self.parent) {
+            Some(group) => group,
+            None => return,
+        };
+
+        if reference.max_occurs.is_many() {
+            self.push_repeated_group(&reference, group);
+            self.ctx.leave_group();
+            return;
+        }
+
+        let start = self.fields.len();
+        group
+            .sequences
+            .into_iter()
+            .for_each(|s| self.push_sequence(s));
+        group.alls.into_iter().for_each(|a| self.push_all(a));
+        group.choices.into_iter().for_each(|c| self.push_choice(c));
+        self.ctx.leave_group();
+
+        if reference.min_occurs == 0 {
+            self.fields[start..]
+                .iter_mut()
+                .for_each(|f| f.required = false);
+        }
+    }
+
+    /// Pushes an array field of a group that may occur several times.
+    /// A group of a single choice is a repeated choice, other groups are
+    /// hoisted into `ctx` as an ObjectImpl holding the fields of one item.
+    fn push_repeated_group(&mut self, reference: &Group, mut group: Group) {
+        let bounds = Bounds {
+            min: reference.min_occurs.max(0) as u64,
+            max: match reference.max_occurs {
+                Occurence::Bound(n) => Some(n as u64),
+                Occurence::Unbounded => None,
+            },
+        };
+
+        if group.sequences.is_empty() && group.alls.is_empty() && group.choices.len() == 1 {
+            let mut choice = group.choices.remove(0);
+            choice.min_occurs = choice.min_occurs.saturating_mul(reference.min_occurs);
+            choice.max_occurs = match (choice.max_occurs, reference.max_occurs) {
+                (Occurence::Bound(m), Occurence::Bound(n)) => Occurence::Bound(m.saturating_mul(n)),
+                _ => Occurence::Unbounded,
+            };
+            return self.push_choice(choice);
+        }
+
+        let group_name = group.name.as_deref().unwrap_or_default().to_camel_case();
+        let name = self
+            .ctx
+            .unique_name(&format!("{}{}", self.parent, group_name));
+        let field_name = name.trim_start_matches(self.parent).to_string();
+
+        let mut fields = Fields::new(&name, self.ctx);
+        group
+            .sequences
+            .into_iter()
+            .for_each(|s| fields.push_sequence(s));
+        group.alls.into_iter().for_each(|a| fields.push_all(a));
+        group
+            .choices
+            .into_iter()
+            .for_each(|c| fields.push_choice(c));
+        let unordered = fields.alls && !fields.sequences;
+        let fields = fields.fields;
+
+        if fields.is_empty() {
+            self.ctx.report.push(
+                Diagnostic::warning("Dropping repeated group without elements")
+                    .in_type(self.parent)
+                    .in_field(field_name),
+            );
+            return;
+        }
+
+        self.ctx.report.push(
+            Diagnostic::warning(format!(
+                "Each item of repeated group `{}` is wrapped in a `{}` element",
+                group.name.as_deref().unwrap_or_default(),
+                field_name
+            ))
+            .in_type(self.parent)
+            .in_field(field_name.as_str()),
+        );
+
+        self.ctx.hoisted.push(Implementation::Object(ObjectImpl {
+            name: name.clone(),
+            base: None,
+            fields,
+            unordered,
+            roots: Vec::new(),
+        }));
+
+        let mut field = Field::new(field_name, TypeName::array(TypeName::object(name)));
+        field.required = bounds.min > 0;
+        field.bounds = Some(bounds);
+        self.fields.push(field);
+    }
+
+    fn push_attribute(&mut self, attr: Attribute) {
+        if let Some(attr) = self.ctx.resolve_attribute(attr, self.parent) {
+            self.fields
+                .extend(attr.into_field(self.parent, &mut self.ctx.report));
+        }
+    }
+
+    fn push_attribute_group(&mut self, reference: AttributeGroup) {
+        let group = match self.ctx.enter_attribute_group(&reference, self.parent) {
+            Some(group) => group,
+            None => return,
+        };
+
+        group
+            .attributes
+            .into_iter()
+            .for_each(|a| self.push_attribute(a));
+        group
+            .attribute_groups
+            .into_iter()
+            .for_each(|g| self.push_attribute_group(g));
+        self.ctx.leave_group();
     }
 }
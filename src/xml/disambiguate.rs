@@ -0,0 +1,251 @@
+use super::{anonymous::VisitElements, Attribute, AttributeGroup, ExpandedName, Kind, Schema};
+use crate::{Context, Diagnostic};
+use heck::CamelCase;
+use std::{collections::HashMap, mem};
+
+impl Schema {
+    /// Renames the types whose name is also defined in another namespace,
+    /// along with the references to them, so every type has a name of its own.
+    ///
+    /// The first definition keeps its name, the others are prefixed by the
+    /// last part of their namespace, such as `CommonAddress` for `Address`
+    /// in `http://example.com/common`.
+    ///
+    /// Run after `hoist_anonymous_types`, so every type has a name.
+    pub fn disambiguate_type_names(&mut self, ctx: &mut Context) {
+        let mut first = HashMap::new();
+        let mut renamed = HashMap::new();
+
+        let names = self
+            .complex_types
+            .iter_mut()
+            .map(|ct| (&mut ct.name, &ct.namespace))
+            .chain(
+                self.simple_types
+                    .iter_mut()
+                    .map(|st| (&mut st.name, &st.namespace)),
+            );
+
+        for (name, namespace) in names {
+            match first.get(name.as_str()) {
+                Some(ns) if ns != namespace => (),
+                Some(_) => continue,
+                None => {
+                    first.insert(name.clone(), namespace.clone());
+                    continue;
+                }
+            }
+
+            let prefix = namespace_prefix(namespace.as_deref());
+            let unique = ctx.unique_name(&format!("{}{}", prefix, name));
+            ctx.report.push(
+                Diagnostic::warning(format!(
+                    "Renaming `{}` to `{}`, another namespace defines a type of the same name",
+                    name, unique
+                ))
+                .in_type(unique.as_str()),
+            );
+
+            let original = mem::replace(name, unique.clone());
+            renamed.insert(ExpandedName::new(namespace.as_deref(), original), unique);
+        }
+
+        if renamed.is_empty() {
+            return;
+        }
+
+        let rename = |kind: &mut Kind| {
+            if let Kind::Object(name) = kind {
+                if let Some(unique) = renamed.get(name) {
+                    name.local = unique.clone();
+                }
+            }
+        };
+        let mut rename_element = |el: &mut super::Element| el.kind.iter_mut().for_each(rename);
+
+        self.elements.visit_elements(&mut rename_element);
+        self.groups.visit_elements(&mut rename_element);
+        self.attributes
+            .iter_mut()
+            .for_each(|a| rename_attribute(a, rename));
+        self.attribute_groups
+            .iter_mut()
+            .for_each(|g| rename_attribute_group(g, rename));
+
+        for ct in &mut self.complex_types {
+            ct.visit_elements(&mut rename_element);
+            ct.attributes
+                .iter_mut()
+                .for_each(|a| rename_attribute(a, rename));
+            ct.attribute_groups
+                .iter_mut()
+                .for_each(|g| rename_attribute_group(g, rename));
+
+            for ext in ct
+                .complex_contents
+                .iter_mut()
+                .flat_map(|cc| cc.extensions.iter_mut())
+            {
+                rename(&mut ext.base);
+                ext.attributes
+                    .iter_mut()
+                    .for_each(|a| rename_attribute(a, rename));
+                ext.attribute_groups
+                    .iter_mut()
+                    .for_each(|g| rename_attribute_group(g, rename));
+            }
+        }
+
+        for st in &mut self.simple_types {
+            st.restrictions.iter_mut().for_each(|r| rename(&mut r.base));
+            if let Some(list) = &mut st.list {
+                list.item_type.iter_mut().for_each(rename);
+            }
+            if let Some(union) = &mut st.union {
+                union.member_types.iter_mut().for_each(rename);
+            }
+        }
+    }
+}
+
+fn rename_attribute(attr: &mut Attribute, rename: impl Fn(&mut Kind)) {
+    attr.kind.iter_mut().for_each(rename);
+}
+
+fn rename_attribute_group(group: &mut AttributeGroup, rename: impl Fn(&mut Kind) + Copy) {
+    group
+        .attributes
+        .iter_mut()
+        .for_each(|a| rename_attribute(a, rename));
+    group
+        .attribute_groups
+        .iter_mut()
+        .for_each(|g| rename_attribute_group(g, rename));
+}
+
+/// The last part of a namespace as a prefix of type names.
+fn namespace_prefix(namespace: Option<&str>) -> String {
+    let segment = namespace
+        .unwrap_or_default()
+        .rsplit(['/', ':', '#'])
+        .find(|s| !s.is_empty())
+        .unwrap_or_default();
+
+    let prefix = segment.to_camel_case();
+    match prefix.chars().next() {
+        Some(c) if c.is_ascii_digit() => format!("Ns{}", prefix),
+        _ => prefix,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(namespace: &str, body: &str) -> Schema {
+        let s = format!(
+            r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns="{0}" xmlns:c="urn:shop:common" targetNamespace="{0}">{1}</xs:schema>"#,
+            namespace, body
+        );
+        crate::xml::de(s.as_bytes()).expect("Deserializing")
+    }
+
+    #[test]
+    fn disambiguate_type_names() {
+        let mut merged = schema(
+            "urn:shop:orders",
+            r#"
+  <xs:complexType name="Address">
+    <xs:sequence>
+      <xs:element name="Street" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:complexType name="Order">
+    <xs:sequence>
+      <xs:element name="Billing" type="Address" />
+      <xs:element name="Shipping" type="c:Address" />
+    </xs:sequence>
+    <xs:attribute name="Kind" type="c:Kind" />
+  </xs:complexType>"#,
+        );
+        let common = schema(
+            "urn:shop:common",
+            r#"
+  <xs:complexType name="Address">
+    <xs:complexContent mixed="false">
+      <xs:extension base="c:Base" />
+    </xs:complexContent>
+  </xs:complexType>
+  <xs:complexType name="Base">
+    <xs:sequence>
+      <xs:element name="City" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:simpleType name="Kind">
+    <xs:restriction base="xs:string" />
+  </xs:simpleType>"#,
+        );
+        merged.complex_types.extend(common.complex_types);
+        merged.simple_types.extend(common.simple_types);
+
+        let mut ctx = Context::new(
+            merged
+                .complex_types
+                .iter()
+                .map(|ct| ct.name.clone())
+                .chain(merged.simple_types.iter().map(|st| st.name.clone())),
+        );
+        merged.disambiguate_type_names(&mut ctx);
+
+        assert_eq!(
+            merged
+                .complex_types
+                .iter()
+                .map(|ct| ct.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Address", "Order", "CommonAddress", "Base"]
+        );
+        assert_eq!(
+            ctx.report.diagnostics,
+            vec![Diagnostic::warning(
+                "Renaming `Address` to `CommonAddress`, another namespace defines a type of the same name"
+            )
+            .in_type("CommonAddress")]
+        );
+
+        let kinds = merged.complex_types[1].sequences[0]
+            .elements()
+            .map(|el| el.kind.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(Kind::Object(ExpandedName::new(
+                    Some("urn:shop:orders"),
+                    "Address"
+                ))),
+                Some(Kind::Object(ExpandedName::new(
+                    Some("urn:shop:common"),
+                    "CommonAddress"
+                ))),
+            ]
+        );
+        assert_eq!(
+            merged.complex_types[2]
+                .extension()
+                .and_then(|ext| ext.base_name()),
+            Some("Base")
+        );
+    }
+
+    #[test]
+    fn namespace_prefixes() {
+        assert_eq!(
+            namespace_prefix(Some("http://example.com/common/")),
+            "Common"
+        );
+        assert_eq!(namespace_prefix(Some("urn:shop:order-lines")), "OrderLines");
+        assert_eq!(namespace_prefix(Some("http://example.com/2020")), "Ns2020");
+        assert_eq!(namespace_prefix(None), "");
+    }
+}
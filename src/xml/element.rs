@@ -1,27 +1,97 @@
-use super::{Kind, Occurence};
+use super::{ComplexType, Kind, Occurence, SimpleType};
+use crate::{Bounds, Diagnostic, Field, Report, TypeName};
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct Element {
+    #[serde(default)]
     pub name: String,
 
-    /// This is only present in 2 fields from billecta.
-    /// Disregard and focus on min_occurs & max_occurs
-    nillable: Option<String>,
+    /// A top level element this element stands for.
+    /// Resolved by `Schema::resolve_element_references`.
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
 
+    /// The element may be present but empty, marked by `xsi:nil`.
     #[serde(default)]
+    pub nillable: bool,
+
+    #[serde(default = "default_min_occurs")]
     pub min_occurs: i8,
     #[serde(default)]
     pub max_occurs: Occurence,
 
     #[serde(rename = "type")]
     pub kind: Option<Kind>,
+
+    /// The value of the element when it is present but empty.
+    /// A missing element has no value.
+    pub default: Option<String>,
+    /// The only value the element may have, also taken when it is empty.
+    pub fixed: Option<String>,
+
+    /// Types defined inline instead of referred to by `type`.
+    /// They are moved out by `Schema::hoist_anonymous_types`.
+    pub complex_type: Option<Box<ComplexType>>,
+    pub simple_type: Option<Box<SimpleType>>,
 }
 
 impl Element {
     pub fn is_optional(&self) -> bool {
         self.min_occurs == 0
     }
+
+    /// True if the element may occur more than once.
+    pub fn is_array(&self) -> bool {
+        self.max_occurs.is_many()
+    }
+
+    /// Creates the field of an element in `parent`.
+    /// Elements without a name are dropped with a warning, and elements
+    /// with `maxOccurs="0"` are dropped as they never occur.
+    /// Elements without a type are of `anyType`.
+    pub fn into_field(self, parent: &str, report: &mut Report) -> Option<Field> {
+        if self.max_occurs == Occurence::Bound(0) {
+            return None;
+        }
+
+        if self.name.is_empty() {
+            report.push(Diagnostic::warning("Dropping element without a name").in_type(parent));
+            return None;
+        }
+
+        let required = !self.is_optional();
+        let is_array = self.is_array();
+
+        let type_name = TypeName::from(self.kind.unwrap_or(Kind::AnyType));
+
+        let (type_name, bounds) = if is_array {
+            let bounds = Bounds {
+                min: self.min_occurs.max(0) as u64,
+                max: match self.max_occurs {
+                    Occurence::Bound(n) => Some(n as u64),
+                    Occurence::Unbounded => None,
+                },
+            };
+            (TypeName::Array(Box::new(type_name)), Some(bounds))
+        } else {
+            (type_name, None)
+        };
+
+        let mut field = Field::new(self.name, type_name);
+        field.required = required;
+        field.nillable = self.nillable;
+        field.default = self.default;
+        field.fixed = self.fixed;
+        field.bounds = bounds;
+
+        Some(field)
+    }
+}
+
+/// XSD defaults to exactly one occurence.
+pub(super) fn default_min_occurs() -> i8 {
+    1
 }
 
 #[cfg(test)]
@@ -42,5 +112,71 @@ mod tests {
         assert_eq!(el.name, "string");
         assert_eq!(el.kind, Some(Kind::String));
         assert!(el.is_optional());
+        assert!(el.is_array());
+        assert!(el.nillable);
+
+        let field = el
+            .clone()
+            .into_field("Strings", &mut Report::default())
+            .expect("Field");
+        assert!(field.nillable);
+        assert!(!field.required);
+    }
+
+    #[test]
+    fn default_occurences() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+   <xs:element name="Amount" type="xs:double" />
+</xs:schema>
+"#;
+        let schema: crate::xml::Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let el = schema.all_elements().next().expect("One element");
+
+        assert_eq!(el.min_occurs, 1);
+        assert_eq!(el.max_occurs, Occurence::Bound(1));
+        assert!(!el.is_optional());
+        assert!(!el.is_array());
+    }
+
+    #[test]
+    fn prohibited_elements() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+   <xs:element name="Legacy" type="xs:string" minOccurs="0" maxOccurs="0" />
+</xs:schema>
+"#;
+        let schema: crate::xml::Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let el = schema.all_elements().next().expect("One element");
+        let mut report = Report::default();
+
+        assert_eq!(el.max_occurs, Occurence::Bound(0));
+        assert_eq!(el.clone().into_field("Order", &mut report), None);
+        assert!(report.is_empty());
+    }
+
+    #[test]
+    fn typeless_elements() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+   <xs:element name="Data" />
+   <xs:element name="Extra" type="xs:anyType" maxOccurs="unbounded" />
+</xs:schema>
+"#;
+        let schema: crate::xml::Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let mut report = Report::default();
+        let fields = schema
+            .all_elements()
+            .filter_map(|el| el.clone().into_field("Event", &mut report))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            fields,
+            vec![
+                Field::new("Data", crate::Primitive::AnyType),
+                Field::new("Extra", TypeName::array(crate::Primitive::AnyType)).bounded(1, None),
+            ]
+        );
+        assert!(report.is_empty());
     }
 }
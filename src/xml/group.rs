@@ -0,0 +1,46 @@
+use super::{element::default_min_occurs, All, Attribute, Choice, Occurence, Sequence};
+
+/// A named group of elements, or a reference to one with `ref`.
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    pub name: Option<String>,
+
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+
+    #[serde(default = "default_min_occurs")]
+    pub min_occurs: i8,
+    #[serde(default)]
+    pub max_occurs: Occurence,
+
+    #[serde(rename = "sequence", default)]
+    pub sequences: Vec<Sequence>,
+
+    #[serde(rename = "all", default)]
+    pub alls: Vec<All>,
+
+    #[serde(rename = "choice", default)]
+    pub choices: Vec<Choice>,
+}
+
+/// A named group of attributes, or a reference to one with `ref`.
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeGroup {
+    pub name: Option<String>,
+
+    #[serde(rename = "ref")]
+    pub reference: Option<String>,
+
+    #[serde(rename = "attribute", default)]
+    pub attributes: Vec<Attribute>,
+
+    #[serde(rename = "attributeGroup", default)]
+    pub attribute_groups: Vec<AttributeGroup>,
+}
+
+/// The name a `ref` refers to, without any namespace prefix.
+pub(crate) fn local_name(reference: &str) -> &str {
+    reference.rsplit(':').next().unwrap_or(reference)
+}
@@ -1,7 +1,11 @@
-use std::{fmt, str};
+use super::{ExpandedName, Namespaces, QName, MS_TYPES_NAMESPACE, XSD_NAMESPACE};
+use std::fmt;
 
 /// The Type of a field, renamed to Kind as to not conflict with
 /// reserved named.
+///
+/// Holds every built-in type of XSD 1.0,
+/// along with the `guid` type of .NET schemas.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Kind {
     Boolean,
@@ -12,58 +16,183 @@ pub enum Kind {
     Base64Binary,
     Guid,
     DateTime,
-    Array(Box<Kind>),
-    Object(String),
+
+    /// Any content at all, also the type of elements without a type.
+    AnyType,
+    AnySimpleType,
+
+    // Primitive types.
+    Decimal,
+    Float,
+    Duration,
+    Time,
+    Date,
+    GYearMonth,
+    GYear,
+    GMonthDay,
+    GDay,
+    GMonth,
+    HexBinary,
+    AnyUri,
+    QName,
+    Notation,
+
+    // Types derived from string.
+    NormalizedString,
+    Token,
+    Language,
+    NmToken,
+    NmTokens,
+    Name,
+    NcName,
+    Id,
+    IdRef,
+    IdRefs,
+    Entity,
+    Entities,
+
+    // Types derived from decimal.
+    Integer,
+    NonPositiveInteger,
+    NegativeInteger,
+    Short,
+    Byte,
+    NonNegativeInteger,
+    UnsignedLong,
+    UnsignedInt,
+    UnsignedShort,
+    UnsignedByte,
+    PositiveInteger,
+
+    /// A type defined by a schema, by its namespace and name.
+    Object(ExpandedName),
 }
 
 impl fmt::Display for Kind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Boolean => f.write_str("boolean"),
-            Self::Int => f.write_str("int"),
-            Self::Long => f.write_str("long"),
-            Self::Double => f.write_str("double"),
-            Self::String => f.write_str("string"),
             Self::Base64Binary => f.write_str("Base64Binary"),
             Self::Guid => f.write_str("Guid"),
             Self::DateTime => f.write_str("DateTime"),
-            Self::Array(inner_kind) => write!(f, "Array<{}>", &inner_kind),
-            Self::Object(name) => f.write_str(&name),
+            Self::Object(name) => f.write_str(&name.local),
+            builtin => f.write_str(builtin.local_name().unwrap_or_default()),
         }
     }
 }
 
-impl str::FromStr for Kind {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            _ if s.eq_ignore_ascii_case("guid") => Ok(Self::Guid),
-            _ if s.eq_ignore_ascii_case("xs:int") => Ok(Self::Int),
-            _ if s.eq_ignore_ascii_case("xs:long") => Ok(Self::Long),
-            _ if s.eq_ignore_ascii_case("xs:string") => Ok(Self::String),
-            _ if s.eq_ignore_ascii_case("xs:double") => Ok(Self::Double),
-            _ if s.eq_ignore_ascii_case("xs:base64Binary") => Ok(Self::Base64Binary),
-            _ if s.eq_ignore_ascii_case("xs:dateTime") => Ok(Self::DateTime),
-            _ if s.eq_ignore_ascii_case("xs:boolean") => Ok(Self::Boolean),
-            s if s.starts_with("ArrayOf") => {
-                let inner_kind = Self::from_str(s.trim_start_matches("ArrayOf"))
-                    .map_err(|err| format!("Parsing Array element from `{}`: {}", s, err))?;
-                Ok(Self::Array(Box::new(inner_kind)))
-            }
-            s if s.is_empty() => Err("Empty Object Kind".into()),
-            s if !s.starts_with("xs") => {
-                if s.chars().next().unwrap().is_lowercase() {
-                    Err(format!("Non upcased Object: `{}`", s))
-                } else {
-                    Ok(Self::Object(s.into()))
-                }
+impl Kind {
+    /// The built-in XSD type with the given local name.
+    pub fn builtin(local: &str) -> Option<Self> {
+        BUILTINS
+            .iter()
+            .find(|(name, _)| *name == local)
+            .map(|(_, kind)| kind.clone())
+    }
+
+    /// The local name of a built-in XSD type.
+    pub fn local_name(&self) -> Option<&'static str> {
+        BUILTINS
+            .iter()
+            .find(|(_, kind)| kind == self)
+            .map(|(name, _)| *name)
+    }
+
+    /// The built-in type this one is derived from,
+    /// None for `anyType`, `anySimpleType`, `guid` and objects.
+    pub fn base(&self) -> Option<Self> {
+        let base = match self {
+            Self::AnyType | Self::AnySimpleType | Self::Guid | Self::Object(_) => return None,
+            Self::NormalizedString => Self::String,
+            Self::Token => Self::NormalizedString,
+            Self::Language | Self::NmToken | Self::Name => Self::Token,
+            Self::NcName => Self::Name,
+            Self::Id | Self::IdRef | Self::Entity => Self::NcName,
+            // List types are derived from their item type.
+            Self::NmTokens => Self::NmToken,
+            Self::IdRefs => Self::IdRef,
+            Self::Entities => Self::Entity,
+            Self::Integer => Self::Decimal,
+            Self::NonPositiveInteger | Self::NonNegativeInteger | Self::Long => Self::Integer,
+            Self::NegativeInteger => Self::NonPositiveInteger,
+            Self::Int => Self::Long,
+            Self::Short => Self::Int,
+            Self::Byte => Self::Short,
+            Self::UnsignedLong | Self::PositiveInteger => Self::NonNegativeInteger,
+            Self::UnsignedInt => Self::UnsignedLong,
+            Self::UnsignedShort => Self::UnsignedInt,
+            Self::UnsignedByte => Self::UnsignedShort,
+            _ => Self::AnySimpleType,
+        };
+
+        Some(base)
+    }
+
+    /// The Kind of a resolved type name.
+    /// Types not built into XSD are objects, keeping their namespace.
+    pub fn from_name(name: &ExpandedName, imports: &[String]) -> Result<Self, String> {
+        match name.namespace.as_deref() {
+            Some(ns) if ns == XSD_NAMESPACE => Self::builtin(&name.local)
+                .ok_or_else(|| format!("Unknown built-in type `{}`", name.local)),
+            Some(ns) if ns == MS_TYPES_NAMESPACE && name.local == "guid" => Ok(Self::Guid),
+            // .NET refers to the types of its imported namespace without a prefix.
+            None if name.local == "guid" && imports.iter().any(|i| i == MS_TYPES_NAMESPACE) => {
+                Ok(Self::Guid)
             }
-            s => Err(format!("Unknown kind `{}`", s)),
+            _ => Ok(Self::Object(name.clone())),
         }
     }
 }
 
+/// The built-in types by their local name in the XSD namespace.
+static BUILTINS: &[(&str, Kind)] = &[
+    ("anyType", Kind::AnyType),
+    ("anySimpleType", Kind::AnySimpleType),
+    ("string", Kind::String),
+    ("boolean", Kind::Boolean),
+    ("decimal", Kind::Decimal),
+    ("float", Kind::Float),
+    ("double", Kind::Double),
+    ("duration", Kind::Duration),
+    ("dateTime", Kind::DateTime),
+    ("time", Kind::Time),
+    ("date", Kind::Date),
+    ("gYearMonth", Kind::GYearMonth),
+    ("gYear", Kind::GYear),
+    ("gMonthDay", Kind::GMonthDay),
+    ("gDay", Kind::GDay),
+    ("gMonth", Kind::GMonth),
+    ("hexBinary", Kind::HexBinary),
+    ("base64Binary", Kind::Base64Binary),
+    ("anyURI", Kind::AnyUri),
+    ("QName", Kind::QName),
+    ("NOTATION", Kind::Notation),
+    ("normalizedString", Kind::NormalizedString),
+    ("token", Kind::Token),
+    ("language", Kind::Language),
+    ("NMTOKEN", Kind::NmToken),
+    ("NMTOKENS", Kind::NmTokens),
+    ("Name", Kind::Name),
+    ("NCName", Kind::NcName),
+    ("ID", Kind::Id),
+    ("IDREF", Kind::IdRef),
+    ("IDREFS", Kind::IdRefs),
+    ("ENTITY", Kind::Entity),
+    ("ENTITIES", Kind::Entities),
+    ("integer", Kind::Integer),
+    ("nonPositiveInteger", Kind::NonPositiveInteger),
+    ("negativeInteger", Kind::NegativeInteger),
+    ("long", Kind::Long),
+    ("int", Kind::Int),
+    ("short", Kind::Short),
+    ("byte", Kind::Byte),
+    ("nonNegativeInteger", Kind::NonNegativeInteger),
+    ("unsignedLong", Kind::UnsignedLong),
+    ("unsignedInt", Kind::UnsignedInt),
+    ("unsignedShort", Kind::UnsignedShort),
+    ("unsignedByte", Kind::UnsignedByte),
+    ("positiveInteger", Kind::PositiveInteger),
+];
+
 impl<'de> serde::de::Visitor<'de> for Visitor {
     type Value = Kind;
 
@@ -74,10 +203,34 @@ impl<'de> serde::de::Visitor<'de> for Visitor {
     where
         E: serde::de::Error,
     {
-        s.parse::<Kind>().map_err(E::custom)
+        resolve(s).map_err(E::custom)
     }
 }
 
+/// Resolves a name written in the document being deserialized.
+fn resolve(s: &str) -> Result<Kind, String> {
+    let name = s.parse::<QName>()?;
+    Namespaces::with_current(|ns| {
+        ns.expand(&name)
+            .and_then(|name| Kind::from_name(&name, &ns.imports))
+    })
+}
+
+/// Deserializes a space separated list of names, such as `memberTypes`.
+pub(crate) fn deserialize_kinds<'de, D>(deserializer: D) -> Result<Vec<Kind>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    use serde::de::{Deserialize, Error};
+
+    String::deserialize(deserializer)?
+        .split_whitespace()
+        .map(|s| resolve(s).map_err(D::Error::custom))
+        .collect()
+}
+
+/// Names are resolved using the namespaces of the document,
+/// so Kinds must be deserialized through `xml::de`.
 impl<'de> serde::de::Deserialize<'de> for Kind {
     fn deserialize<D>(deserializer: D) -> Result<Kind, D::Error>
     where
@@ -88,3 +241,39 @@ impl<'de> serde::de::Deserialize<'de> for Kind {
 }
 
 struct Visitor;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_hierarchy() {
+        assert_eq!(Kind::builtin("unsignedByte"), Some(Kind::UnsignedByte));
+        assert_eq!(Kind::builtin("NCName"), Some(Kind::NcName));
+        assert_eq!(Kind::builtin("anyType"), Some(Kind::AnyType));
+        assert_eq!(Kind::builtin("Guid"), None);
+        assert_eq!(Kind::AnyUri.local_name(), Some("anyURI"));
+        assert_eq!(
+            Kind::Object(ExpandedName::new(None, "A")).local_name(),
+            None
+        );
+
+        let mut ancestors = Vec::new();
+        let mut kind = Kind::Byte;
+        while let Some(base) = kind.base() {
+            ancestors.push(base.to_string());
+            kind = base;
+        }
+        assert_eq!(
+            ancestors,
+            vec![
+                "short",
+                "int",
+                "long",
+                "integer",
+                "decimal",
+                "anySimpleType"
+            ]
+        );
+    }
+}
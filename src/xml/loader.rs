@@ -0,0 +1,388 @@
+use super::{ExpandedName, Schema};
+use crate::{Diagnostic, Report};
+use anyhow::{Context, Result};
+use std::{
+    collections::HashSet,
+    fs, mem,
+    path::{Path, PathBuf},
+};
+
+/// Finds the schemas referenced by `xs:include` and `xs:import`.
+pub trait Resolver {
+    /// The file of a schema referenced from the schema in `base`.
+    /// `location` is the `schemaLocation`, which imports may leave out.
+    /// Returning None leaves the reference unresolved.
+    fn resolve(
+        &self,
+        base: &Path,
+        namespace: Option<&str>,
+        location: Option<&str>,
+    ) -> Option<PathBuf>;
+}
+
+/// Resolves locations relative to the directory of the referencing schema.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RelativeResolver;
+
+impl Resolver for RelativeResolver {
+    fn resolve(
+        &self,
+        base: &Path,
+        _namespace: Option<&str>,
+        location: Option<&str>,
+    ) -> Option<PathBuf> {
+        let location = location?;
+        match base.parent() {
+            Some(dir) => Some(dir.join(location)),
+            None => Some(PathBuf::from(location)),
+        }
+    }
+}
+
+/// Closures can be used as resolvers, such as for a local catalog.
+impl<F> Resolver for F
+where
+    F: Fn(&Path, Option<&str>, Option<&str>) -> Option<PathBuf>,
+{
+    fn resolve(
+        &self,
+        base: &Path,
+        namespace: Option<&str>,
+        location: Option<&str>,
+    ) -> Option<PathBuf> {
+        self(base, namespace, location)
+    }
+}
+
+/// Loads a schema along with every schema it includes or imports,
+/// merged into a single Schema.
+#[derive(Debug, Default)]
+pub struct Loader<R = RelativeResolver> {
+    resolver: R,
+    loaded: Vec<PathBuf>,
+    attempted: Vec<PathBuf>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<R> Loader<R>
+where
+    R: Resolver,
+{
+    pub fn with_resolver(resolver: R) -> Self {
+        Self {
+            resolver,
+            loaded: Vec::new(),
+            attempted: Vec::new(),
+        }
+    }
+
+    /// All files read by the last call to `load`.
+    pub fn loaded(&self) -> &[PathBuf] {
+        &self.loaded
+    }
+
+    /// All files the last call to `load` tried to read, including the ones
+    /// that are missing or failed to parse.
+    pub fn attempted(&self) -> &[PathBuf] {
+        &self.attempted
+    }
+
+    /// Loads the schema at `path`, failing only if that schema can't be read.
+    /// Problems with the schemas it refers to are reported instead.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(Schema, Report)> {
+        let path = path.as_ref();
+        self.loaded.clear();
+        self.attempted = vec![path.to_path_buf()];
+        let schema = read(path)?;
+
+        let mut merged = Schema::default();
+        let mut report = Report::default();
+
+        self.visit(path, schema, &mut merged, &mut Vec::new(), &mut report);
+
+        Ok((merged, report))
+    }
+
+    /// Merges `schema` and then the schemas it refers to, depth first.
+    /// `stack` holds the schemas currently being visited.
+    fn visit(
+        &mut self,
+        path: &Path,
+        mut schema: Schema,
+        merged: &mut Schema,
+        stack: &mut Vec<PathBuf>,
+        report: &mut Report,
+    ) {
+        let id = identity(path);
+        self.loaded.push(id.clone());
+        stack.push(id);
+
+        let includes = mem::take(&mut schema.includes);
+        let imports = mem::take(&mut schema.imports);
+        merge(merged, schema, path, report);
+
+        let references = includes
+            .into_iter()
+            .map(|i| ("include", None, Some(i.schema_location)))
+            .chain(
+                imports
+                    .into_iter()
+                    .map(|i| ("import", i.namespace, i.schema_location)),
+            );
+
+        for (kind, namespace, location) in references {
+            let target =
+                match self
+                    .resolver
+                    .resolve(path, namespace.as_deref(), location.as_deref())
+                {
+                    Some(target) => target,
+                    None => {
+                        let name = location.or(namespace).unwrap_or_default();
+                        report.push(
+                            Diagnostic::warning(format!("Unresolved {} `{}`", kind, name))
+                                .at(path.display().to_string()),
+                        );
+                        continue;
+                    }
+                };
+
+            let id = identity(&target);
+            if stack.contains(&id) {
+                report.push(
+                    Diagnostic::warning(format!("Cyclic {} of `{}`", kind, target.display()))
+                        .at(path.display().to_string()),
+                );
+                continue;
+            }
+
+            // Already merged through another schema.
+            if self.loaded.contains(&id) {
+                continue;
+            }
+
+            self.attempted.push(target.clone());
+            match read(&target) {
+                Ok(schema) => self.visit(&target, schema, merged, stack, report),
+                Err(err) => report
+                    .push(Diagnostic::error(format!("{:#}", err)).at(path.display().to_string())),
+            }
+        }
+
+        stack.pop();
+    }
+}
+
+fn read(path: &Path) -> Result<Schema> {
+    let file = fs::File::open(path).with_context(|| format!("Opening `{}`", path.display()))?;
+    super::de(file).with_context(|| format!("Parsing `{}`", path.display()))
+}
+
+/// The same file may be referred to through different paths.
+fn identity(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Moves all definitions of `schema` into `merged`.
+/// Types another schema already defined in the same namespace are dropped,
+/// types of the same name in other namespaces are kept.
+fn merge(merged: &mut Schema, schema: Schema, path: &Path, report: &mut Report) {
+    let mut names = merged
+        .complex_types
+        .iter()
+        .map(|ct| ct.expanded_name())
+        .chain(merged.simple_types.iter().map(|st| st.expanded_name()))
+        .collect::<HashSet<_>>();
+
+    let mut is_new = |name: ExpandedName| {
+        if names.contains(&name) {
+            report.push(
+                Diagnostic::warning(format!("Dropping duplicate definition of `{}`", name.local))
+                    .at(path.display().to_string()),
+            );
+            return false;
+        }
+        names.insert(name);
+        true
+    };
+
+    for ct in schema.complex_types {
+        if is_new(ct.expanded_name()) {
+            merged.complex_types.push(ct);
+        }
+    }
+
+    for st in schema.simple_types {
+        if is_new(st.expanded_name()) {
+            merged.simple_types.push(st);
+        }
+    }
+
+    merged.elements.extend(schema.elements);
+    merged.groups.extend(schema.groups);
+    merged.attribute_groups.extend(schema.attribute_groups);
+    merged.attributes.extend(schema.attributes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn write_schema(dir: &Path, name: &str, body: &str) -> PathBuf {
+        let path = dir.join(name);
+        fs::create_dir_all(path.parent().unwrap()).expect("Creating directory");
+        fs::write(
+            &path,
+            format!(
+                r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">{}</xs:schema>"#,
+                body
+            ),
+        )
+        .expect("Writing schema");
+        path
+    }
+
+    #[test]
+    fn load_includes_and_imports() {
+        let dir = env::temp_dir().join(format!("xsd-test-loader-{}", std::process::id()));
+        let root = write_schema(
+            &dir,
+            "root.xsd",
+            r#"
+  <xs:include schemaLocation="common/types.xsd" />
+  <xs:import namespace="urn:catalog" />
+  <xs:import namespace="urn:unknown" />
+  <xs:complexType name="Root">
+    <xs:sequence>
+      <xs:element name="Id" type="Id" />
+    </xs:sequence>
+  </xs:complexType>"#,
+        );
+        write_schema(
+            &dir,
+            "common/types.xsd",
+            r#"
+  <xs:include schemaLocation="../root.xsd" />
+  <xs:simpleType name="Id">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="A" />
+    </xs:restriction>
+  </xs:simpleType>"#,
+        );
+        let catalog = write_schema(
+            &dir,
+            "catalog/types.xsd",
+            r#"
+  <xs:simpleType name="Id">
+    <xs:restriction base="xs:string" />
+  </xs:simpleType>
+  <xs:complexType name="Cataloged" />"#,
+        );
+
+        let resolver =
+            |base: &Path, namespace: Option<&str>, location: Option<&str>| match namespace {
+                Some("urn:catalog") => Some(catalog.clone()),
+                _ => RelativeResolver.resolve(base, namespace, location),
+            };
+
+        let mut loader = Loader::with_resolver(resolver);
+        let (schema, report) = loader.load(&root).expect("Loading");
+
+        assert_eq!(loader.loaded().len(), 3);
+        assert_eq!(loader.attempted().len(), 3);
+        assert_eq!(
+            schema
+                .complex_types
+                .iter()
+                .map(|ct| ct.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Root", "Cataloged"]
+        );
+        assert_eq!(schema.simple_types.len(), 1);
+
+        let messages = report
+            .diagnostics
+            .iter()
+            .map(|d| d.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].starts_with("Cyclic include of"));
+        assert_eq!(messages[1], "Dropping duplicate definition of `Id`");
+        assert_eq!(messages[2], "Unresolved import `urn:unknown`");
+
+        let mut loader = Loader::new();
+        assert!(loader.load(dir.join("missing.xsd")).is_err());
+        assert_eq!(loader.attempted(), &[dir.join("missing.xsd")]);
+    }
+
+    #[test]
+    fn attempt_schemas_that_fail_to_load() {
+        let dir = env::temp_dir().join(format!("xsd-test-attempted-{}", std::process::id()));
+        let root = write_schema(
+            &dir,
+            "root.xsd",
+            r#"
+  <xs:include schemaLocation="broken.xsd" />
+  <xs:include schemaLocation="missing.xsd" />"#,
+        );
+        fs::write(dir.join("broken.xsd"), "<xs:schema").expect("Writing schema");
+
+        let mut loader = Loader::new();
+        let (_, report) = loader.load(&root).expect("Loading");
+
+        assert_eq!(loader.loaded().len(), 1);
+        assert_eq!(
+            loader.attempted(),
+            &[root, dir.join("broken.xsd"), dir.join("missing.xsd")]
+        );
+        assert_eq!(report.diagnostics.len(), 2);
+    }
+
+    #[test]
+    fn keep_types_of_other_namespaces() {
+        let dir = env::temp_dir().join(format!("xsd-test-namespaces-{}", std::process::id()));
+        let root = write_schema(
+            &dir,
+            "root.xsd",
+            r#"
+  <xs:import namespace="urn:common" schemaLocation="common.xsd" />
+  <xs:complexType name="Address">
+    <xs:sequence>
+      <xs:element name="Street" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>"#,
+        );
+        fs::write(
+            dir.join("common.xsd"),
+            r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" targetNamespace="urn:common">
+  <xs:complexType name="Address">
+    <xs:sequence>
+      <xs:element name="City" type="xs:string" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>"#,
+        )
+        .expect("Writing schema");
+
+        let (schema, report) = Loader::new().load(&root).expect("Loading");
+
+        assert!(report.is_empty());
+        assert_eq!(
+            schema
+                .complex_types
+                .iter()
+                .map(|ct| ct.expanded_name())
+                .collect::<Vec<_>>(),
+            vec![
+                ExpandedName::new(None, "Address"),
+                ExpandedName::new(Some("urn:common"), "Address"),
+            ]
+        );
+    }
+}
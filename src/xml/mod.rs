@@ -1,11 +1,19 @@
+mod anonymous;
+mod attribute;
+mod choice;
 mod complex_type;
+mod disambiguate;
 mod element;
+mod group;
 mod kind;
+mod loader;
+mod namespace;
 mod occurence;
+mod reference;
 mod schema;
 mod simple_type;
 
 pub use {
-    complex_type::ComplexType, element::*, kind::*, occurence::*, schema::*,
-    simple_type::SimpleType,
+    attribute::*, choice::Choice, complex_type::ComplexType, element::*, group::*, kind::*,
+    loader::*, namespace::*, occurence::*, schema::*, simple_type::SimpleType,
 };
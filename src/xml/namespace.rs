@@ -0,0 +1,258 @@
+use ::xml::reader::{self, EventReader, XmlEvent};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt, str,
+};
+
+/// Namespace of the XML Schema built-in types.
+pub static XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema";
+
+/// Namespace of the types .NET adds to its schemas, such as `guid`.
+pub static MS_TYPES_NAMESPACE: &str = "http://microsoft.com/wsdl/types/";
+
+thread_local! {
+    /// Namespaces of the document currently being deserialized by `xml::de`.
+    static SCOPE: RefCell<Namespaces> = RefCell::new(Namespaces::default());
+}
+
+/// A name as written in the schema, such as `xs:string`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QName {
+    pub prefix: Option<String>,
+    pub local: String,
+}
+
+impl str::FromStr for QName {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, local) = match s.find(':') {
+            Some(i) => (Some(&s[..i]), &s[i + 1..]),
+            None => (None, s),
+        };
+
+        if local.is_empty() || prefix == Some("") {
+            return Err(format!("Invalid name `{}`", s));
+        }
+
+        Ok(Self {
+            prefix: prefix.map(String::from),
+            local: local.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for QName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.prefix {
+            Some(prefix) => write!(f, "{}:{}", prefix, self.local),
+            None => f.write_str(&self.local),
+        }
+    }
+}
+
+/// A name identified by its namespace rather than a prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExpandedName {
+    pub namespace: Option<String>,
+    pub local: String,
+}
+
+impl ExpandedName {
+    pub fn new(namespace: Option<&str>, local: impl Into<String>) -> Self {
+        Self {
+            namespace: namespace.map(String::from),
+            local: local.into(),
+        }
+    }
+}
+
+/// The namespaces declared in a schema document.
+///
+/// Declarations on any element of the schema are considered, so a prefix
+/// may be declared on the element using it rather than on the root.
+/// Prefixes bound to different namespaces by different elements can't be
+/// told apart, and names using them fail to resolve.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Namespaces {
+    /// Namespace by prefix. The default namespace has an empty prefix.
+    pub prefixes: HashMap<String, String>,
+    /// Prefixes bound to more than one namespace.
+    pub ambiguous: HashSet<String>,
+    /// The namespace of the types the schema defines.
+    pub target: Option<String>,
+    /// Namespaces imported by the schema.
+    pub imports: Vec<String>,
+}
+
+impl Namespaces {
+    /// Collects the namespace declarations of a document, which serde can't see.
+    /// Only elements of the XML Schema namespace are considered, as others
+    /// such as the XHTML of documentation never refer to types.
+    pub fn scan(document: &[u8]) -> Result<Self, reader::Error> {
+        let mut res = Self::default();
+        let mut root = true;
+
+        for event in EventReader::new(document) {
+            let (name, attributes, namespace) = match event? {
+                XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } => (name, attributes, namespace),
+                _ => continue,
+            };
+
+            let attribute = |local: &str| {
+                attributes
+                    .iter()
+                    .find(|a| a.name.prefix.is_none() && a.name.local_name == local)
+                    .map(|a| a.value.clone())
+            };
+
+            if name.namespace.as_deref() != Some(XSD_NAMESPACE) {
+                continue;
+            }
+
+            // Each element carries all namespaces in scope, not only its own.
+            let declared = namespace
+                .into_iter()
+                .filter(|(prefix, uri)| *prefix != "xml" && *prefix != "xmlns" && !uri.is_empty());
+            for (prefix, uri) in declared {
+                match res.prefixes.get(prefix) {
+                    Some(bound) if bound != uri => {
+                        res.ambiguous.insert(prefix.to_string());
+                    }
+                    Some(_) => (),
+                    None => {
+                        res.prefixes.insert(prefix.to_string(), uri.to_string());
+                    }
+                }
+            }
+
+            if root {
+                res.target = attribute("targetNamespace");
+                root = false;
+            } else if name.local_name == "import" {
+                res.imports.extend(attribute("namespace"));
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Replaces the prefix of a name by its namespace.
+    /// Names without a prefix are in the default namespace, if any.
+    pub fn expand(&self, name: &QName) -> Result<ExpandedName, String> {
+        let prefix = name.prefix.as_deref().unwrap_or_default();
+        if self.ambiguous.contains(prefix) {
+            return Err(format!(
+                "`{}` is ambiguous, the schema binds its prefix to several namespaces",
+                name
+            ));
+        }
+
+        let namespace = match &name.prefix {
+            Some(prefix) => match self.prefixes.get(prefix) {
+                Some(uri) => Some(uri.clone()),
+                None => return Err(format!("Undeclared prefix in `{}`", name)),
+            },
+            None => self.prefixes.get("").cloned(),
+        };
+
+        Ok(ExpandedName {
+            namespace,
+            local: name.local.clone(),
+        })
+    }
+
+    /// Runs `f` with these namespaces in scope of `with_current`.
+    pub(crate) fn scope<T>(self, f: impl FnOnce() -> T) -> T {
+        let previous = SCOPE.with(|scope| scope.replace(self));
+        let res = f();
+        SCOPE.with(|scope| scope.replace(previous));
+        res
+    }
+
+    /// Calls `f` with the namespaces of the document being deserialized.
+    pub(crate) fn with_current<T>(f: impl FnOnce(&Namespaces) -> T) -> T {
+        SCOPE.with(|scope| f(&scope.borrow()))
+    }
+}
+
+/// The target namespace of the document being deserialized.
+pub(crate) fn target_namespace() -> Option<String> {
+    Namespaces::with_current(|ns| ns.target.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_and_expand() {
+        let s = r#"
+<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns="urn:orders" xmlns:c="urn:common" targetNamespace="urn:orders">
+  <xsd:import namespace="urn:common" schemaLocation="common.xsd" />
+</xsd:schema>
+"#;
+
+        let ns = Namespaces::scan(s.as_bytes()).expect("Scanning");
+        assert_eq!(ns.target.as_deref(), Some("urn:orders"));
+        assert_eq!(ns.imports, vec![String::from("urn:common")]);
+
+        let expand = |s: &str| ns.expand(&s.parse().expect("Valid QName"));
+        assert_eq!(
+            expand("xsd:string"),
+            Ok(ExpandedName {
+                namespace: Some(XSD_NAMESPACE.into()),
+                local: "string".into(),
+            })
+        );
+        assert_eq!(
+            expand("Order").unwrap().namespace.as_deref(),
+            Some("urn:orders")
+        );
+        assert_eq!(
+            expand("c:Address").unwrap().namespace.as_deref(),
+            Some("urn:common")
+        );
+        assert!(expand("x:Unknown").is_err());
+        assert!("xs:".parse::<QName>().is_err());
+    }
+
+    #[test]
+    fn scan_inner_declarations() {
+        let s = r#"
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Order" xmlns:c="urn:common">
+    <xs:sequence>
+      <xs:element name="Address" type="c:Address" />
+      <xs:element name="Total" type="t:Amount" xmlns:t="urn:one" />
+      <xs:element name="Tax" type="t:Amount" xmlns:t="urn:two" />
+    </xs:sequence>
+  </xs:complexType>
+  <xs:annotation>
+    <xs:documentation>
+      <p xmlns="http://www.w3.org/1999/xhtml">Orders</p>
+    </xs:documentation>
+  </xs:annotation>
+</xs:schema>
+"#;
+
+        let ns = Namespaces::scan(s.as_bytes()).expect("Scanning");
+        let expand = |s: &str| ns.expand(&s.parse().expect("Valid QName"));
+        assert_eq!(
+            expand("c:Address"),
+            Ok(ExpandedName::new(Some("urn:common"), "Address"))
+        );
+        assert_eq!(expand("Order"), Ok(ExpandedName::new(None, "Order")));
+        assert_eq!(
+            expand("t:Amount"),
+            Err(
+                "`t:Amount` is ambiguous, the schema binds its prefix to several namespaces".into()
+            )
+        );
+    }
+}
@@ -4,9 +4,20 @@ pub enum Occurence {
     Unbounded,
 }
 
+impl Occurence {
+    /// True if more than one item may occur.
+    pub fn is_many(&self) -> bool {
+        match self {
+            Self::Bound(n) => *n > 1,
+            Self::Unbounded => true,
+        }
+    }
+}
+
+/// XSD defaults to exactly one occurence.
 impl Default for Occurence {
     fn default() -> Self {
-        Self::Unbounded
+        Self::Bound(1)
     }
 }
 
@@ -0,0 +1,116 @@
+use super::{anonymous::VisitElements, Element, Schema};
+use crate::{Diagnostic, Report};
+use std::collections::HashMap;
+
+impl Schema {
+    /// Replaces elements referring to top level elements by `ref` with the
+    /// name, type, nillability and values of the referenced element.
+    /// The occurences stay those of the referring element.
+    ///
+    /// Run after `hoist_anonymous_types`, so every referenced element has a type name.
+    pub fn resolve_element_references(&mut self, report: &mut Report) {
+        let globals = self
+            .elements
+            .iter()
+            .map(|el| (el.name.clone(), el.clone()))
+            .collect::<HashMap<_, _>>();
+
+        for ct in &mut self.complex_types {
+            let parent = ct.name.clone();
+            ct.visit_elements(&mut |el| resolve(el, &globals, &parent, report));
+        }
+
+        for group in &mut self.groups {
+            let parent = group.name.clone().unwrap_or_default();
+            group.visit_elements(&mut |el| resolve(el, &globals, &parent, report));
+        }
+    }
+}
+
+fn resolve(
+    el: &mut Element,
+    globals: &HashMap<String, Element>,
+    parent: &str,
+    report: &mut Report,
+) {
+    let reference = match el.reference.take() {
+        Some(reference) => reference,
+        None => return,
+    };
+
+    match globals.get(super::local_name(&reference)) {
+        Some(global) => {
+            el.name = global.name.clone();
+            el.kind = global.kind.clone();
+            el.nillable = global.nillable;
+            el.default = global.default.clone();
+            el.fixed = global.fixed.clone();
+        }
+        None => {
+            report.push(
+                Diagnostic::warning(format!("Unresolved element `{}`", reference)).in_type(parent),
+            );
+            el.name = super::local_name(&reference).to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xml::{ExpandedName, Kind, Occurence};
+
+    #[test]
+    fn resolve_element_references() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:tns="urn:orders">
+  <xs:element name="Note" type="xs:string" nillable="true" />
+  <xs:element name="Line">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="Id" type="xs:int" />
+      </xs:sequence>
+    </xs:complexType>
+  </xs:element>
+  <xs:complexType name="Order">
+    <xs:sequence>
+      <xs:element ref="tns:Line" maxOccurs="unbounded" />
+      <xs:element ref="Note" minOccurs="0" />
+      <xs:element ref="Missing" />
+    </xs:sequence>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let mut schema: Schema = crate::xml::de(s.as_bytes()).expect("Deserializing");
+        let mut ctx = crate::Context::new(schema.complex_types.iter().map(|ct| ct.name.clone()));
+        schema.hoist_anonymous_types(&mut ctx);
+        schema.resolve_element_references(&mut ctx.report);
+
+        let elements = schema.complex_types[0].sequences[0]
+            .elements()
+            .collect::<Vec<_>>();
+        assert_eq!(elements[0].name, "Line");
+        assert_eq!(
+            elements[0].kind,
+            Some(Kind::Object(ExpandedName::new(None, "Line")))
+        );
+        assert_eq!(elements[0].max_occurs, Occurence::Unbounded);
+        assert_eq!(elements[1].name, "Note");
+        assert_eq!(elements[1].kind, Some(Kind::String));
+        assert!(elements[1].is_optional());
+        assert!(elements[1].nillable);
+        assert_eq!(elements[2].name, "Missing");
+        assert_eq!(elements[2].kind, None);
+
+        assert_eq!(ctx.report.diagnostics.len(), 1);
+        assert_eq!(
+            ctx.report.diagnostics[0].message,
+            "Unresolved element `Missing`"
+        );
+        assert_eq!(
+            ctx.report.diagnostics[0].type_name.as_deref(),
+            Some("Order")
+        );
+    }
+}
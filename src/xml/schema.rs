@@ -1,9 +1,17 @@
-pub use super::{ComplexType, Element, Kind, Occurence, SimpleType};
+pub use super::{
+    Attribute, AttributeGroup, Choice, ComplexType, Element, Group, Kind, Namespaces, SimpleType,
+};
 
 // use crate::{Field, ObjectImpl, TypeName};
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Default, serde::Deserialize, PartialEq, Eq)]
 pub struct Schema {
+    #[serde(rename = "include", default)]
+    pub includes: Vec<Include>,
+
+    #[serde(rename = "import", default)]
+    pub imports: Vec<Import>,
+
     #[serde(rename = "element", default)]
     pub elements: Vec<Element>,
 
@@ -12,6 +20,15 @@ pub struct Schema {
 
     #[serde(rename = "complexType", default)]
     pub complex_types: Vec<ComplexType>,
+
+    #[serde(rename = "group", default)]
+    pub groups: Vec<Group>,
+
+    #[serde(rename = "attributeGroup", default)]
+    pub attribute_groups: Vec<AttributeGroup>,
+
+    #[serde(rename = "attribute", default)]
+    pub attributes: Vec<Attribute>,
 }
 
 impl Schema {
@@ -19,13 +36,28 @@ impl Schema {
         self.elements.iter().chain(
             self.complex_types
                 .iter()
-                .map(|ct| ct.sequences.iter().map(|seq| seq.elements.iter()).flatten())
+                .map(|ct| ct.sequences.iter().map(|seq| seq.elements()).flatten())
                 .flatten(),
         )
     }
 }
 
+/// Another schema with the same target namespace.
+#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Include {
+    pub schema_location: String,
+}
+
+/// Another schema, usually with a different namespace.
 #[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Import {
+    pub namespace: Option<String>,
+    pub schema_location: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
 pub struct ComplexContent {
     mixed: bool,
 
@@ -33,34 +65,141 @@ pub struct ComplexContent {
     pub extensions: Vec<Extension>,
 }
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
 pub struct Extension {
-    pub base: String,
+    pub base: Kind,
 
     #[serde(rename = "sequence", default)]
     pub sequences: Vec<Sequence>,
+
+    #[serde(rename = "all", default)]
+    pub alls: Vec<All>,
+
+    #[serde(rename = "choice", default)]
+    pub choices: Vec<Choice>,
+
+    #[serde(rename = "group", default)]
+    pub groups: Vec<Group>,
+
+    #[serde(rename = "attribute", default)]
+    pub attributes: Vec<Attribute>,
+
+    #[serde(rename = "attributeGroup", default)]
+    pub attribute_groups: Vec<AttributeGroup>,
 }
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+impl Extension {
+    /// The name of the base type, None if it's a built-in type such as `anyType`.
+    pub fn base_name(&self) -> Option<&str> {
+        match &self.base {
+            Kind::Object(name) => Some(&name.local),
+            _ => None,
+        }
+    }
+}
+
+/// Content whose parts appear in the order they are declared.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Sequence {
-    #[serde(rename = "element")]
+    /// The elements, choices, groups and nested sequences, in document order.
+    pub particles: Vec<Particle>,
+}
+
+/// A part of a sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Particle {
+    Element(Element),
+    Choice(Choice),
+    Group(Group),
+    Sequence(Sequence),
+}
+
+impl Sequence {
+    /// The elements directly in the sequence.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.particles.iter().filter_map(|p| match p {
+            Particle::Element(el) => Some(el),
+            _ => None,
+        })
+    }
+}
+
+/// The derived Deserialize would collect each kind of particle separately,
+/// losing their order, so they are read one by one instead.
+/// Anything else, such as annotations, is skipped.
+impl<'de> serde::Deserialize<'de> for Sequence {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = Sequence;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a sequence")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut particles = Vec::new();
+                while let Some(key) = map.next_key::<String>()? {
+                    let particle = match key.as_str() {
+                        "element" => Particle::Element(map.next_value()?),
+                        "choice" => Particle::Choice(map.next_value()?),
+                        "group" => Particle::Group(map.next_value()?),
+                        "sequence" => Particle::Sequence(map.next_value()?),
+                        _ => {
+                            map.next_value::<serde::de::IgnoredAny>()?;
+                            continue;
+                        }
+                    };
+                    particles.push(particle);
+                }
+
+                Ok(Sequence { particles })
+            }
+        }
+
+        deserializer.deserialize_struct(
+            "Sequence",
+            &["element", "choice", "group", "sequence"],
+            Visitor,
+        )
+    }
+}
+
+/// Elements that may appear in any order.
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+pub struct All {
+    #[serde(rename = "element", default)]
     pub elements: Vec<Element>,
 }
 
-pub fn de<'de, T, R>(r: R) -> Result<T, serde_xml_rs::Error>
+/// Deserializes a document, resolving type names with the
+/// namespaces declared on its root element.
+pub fn de<'de, T, R>(mut r: R) -> Result<T, serde_xml_rs::Error>
 where
     R: std::io::Read,
     T: serde::Deserialize<'de>,
 {
-    let mut de = serde_xml_rs::Deserializer::new_from_reader(r).non_contiguous_seq_elements(true);
+    let mut document = Vec::new();
+    r.read_to_end(&mut document)?;
 
-    T::deserialize(&mut de)
+    let namespaces = Namespaces::scan(&document)?;
+    let mut de = serde_xml_rs::Deserializer::new_from_reader(document.as_slice())
+        .non_contiguous_seq_elements(true);
+
+    namespaces.scope(|| T::deserialize(&mut de))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_xml_rs as xml;
+    use crate::xml::{ExpandedName, Occurence};
 
     #[test]
     fn parse_a_little_of_everything() {
@@ -145,10 +284,44 @@ mod tests {
         let _: Schema = super::de(s.as_bytes()).expect("Parsing");
     }
 
+    #[test]
+    fn parse_with_namespaces() {
+        let s = r#"
+<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns="urn:orders" targetNamespace="urn:orders">
+  <xsd:complexType name="order">
+    <xsd:sequence>
+      <xsd:element name="Id" type="xsd:long" />
+      <xsd:element name="Line" type="line" />
+    </xsd:sequence>
+  </xsd:complexType>
+</xsd:schema>
+"#;
+
+        let schema: Schema = super::de(s.as_bytes()).expect("Parsing schema with namespaces");
+        let ct = &schema.complex_types[0];
+        assert_eq!(ct.namespace.as_deref(), Some("urn:orders"));
+
+        let kinds = ct.sequences[0]
+            .elements()
+            .map(|el| el.kind.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(Kind::Long),
+                Some(Kind::Object(ExpandedName::new(Some("urn:orders"), "line")))
+            ]
+        );
+
+        let undeclared = s.replace("xsd:long", "x:long");
+        assert!(super::de::<Schema, _>(undeclared.as_bytes()).is_err());
+    }
+
     #[test]
     fn parse_complex_type() {
         let s = r#"
 <xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+ <xs:import namespace="http://microsoft.com/wsdl/types/" />
  <xs:complexType name="AccountingExportCreation">
     <xs:sequence>
       <xs:element minOccurs="1" maxOccurs="1" name="CreditorPublicId" type="guid" />
@@ -163,10 +336,12 @@ mod tests {
 </xs:schema>
 "#;
 
-        let schema: Schema = xml::from_str(&s).expect("Parsing schema with simple type");
+        let schema: Schema = super::de(s.as_bytes()).expect("Parsing schema with simple type");
         assert_eq!(schema.complex_types.len(), 1);
         assert_eq!(schema.complex_types[0].sequences.len(), 1);
-        let elements = &schema.complex_types[0].sequences[0].elements;
+        let elements = schema.complex_types[0].sequences[0]
+            .elements()
+            .collect::<Vec<_>>();
         assert_eq!(elements.len(), 7);
         assert_eq!(elements[0].min_occurs, 1);
         assert_eq!(elements[0].max_occurs, Occurence::Bound(1));
@@ -202,13 +377,48 @@ mod tests {
             1
         );
         let ext = &schema.complex_types[0].complex_contents[0].extensions[0];
-        assert_eq!(ext.base, "Attribute");
+        assert_eq!(ext.base_name(), Some("Attribute"));
         assert_eq!(ext.sequences.len(), 1);
-        assert_eq!(ext.sequences[0].elements.len(), 1);
-        assert_eq!(ext.sequences[0].elements[0].min_occurs, 1);
-        assert_eq!(ext.sequences[0].elements[0].max_occurs, Occurence::Bound(1));
-        assert_eq!(ext.sequences[0].elements[0].name, "DefaultValue");
-        assert_eq!(ext.sequences[0].elements[0].kind, Some(Kind::Int));
+        let elements = ext.sequences[0].elements().collect::<Vec<_>>();
+        assert_eq!(elements.len(), 1);
+        assert_eq!(elements[0].min_occurs, 1);
+        assert_eq!(elements[0].max_occurs, Occurence::Bound(1));
+        assert_eq!(elements[0].name, "DefaultValue");
+        assert_eq!(elements[0].kind, Some(Kind::Int));
+    }
+
+    #[test]
+    fn parse_complex_type_all() {
+        let xml = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Address">
+    <xs:all>
+      <xs:element name="Street" type="xs:string" />
+      <xs:element minOccurs="0" name="City" type="xs:string" />
+    </xs:all>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+        let schema: Schema = super::de(xml.as_bytes()).expect("Parsing");
+        assert_eq!(schema.complex_types[0].alls[0].elements.len(), 2);
+
+        let object = schema
+            .complex_types
+            .into_iter()
+            .next()
+            .unwrap()
+            .into_object_impl(&mut crate::Context::default())
+            .expect("Creating ObjectImpl");
+
+        assert!(object.unordered);
+        assert_eq!(
+            object.fields,
+            vec![
+                crate::Field::new("Street", crate::Primitive::String),
+                crate::Field::new("City", crate::Primitive::String).optional(),
+            ]
+        );
     }
 
     #[test]
@@ -225,7 +435,7 @@ mod tests {
 </xs:schema>
 "#;
 
-        let schema: Schema = xml::from_str(&s).expect("Parsing schema with simple type");
+        let schema: Schema = super::de(s.as_bytes()).expect("Parsing schema with simple type");
         assert_eq!(schema.simple_types.len(), 1);
         assert_eq!(schema.simple_types[0].name, "AccountingExportFormatType");
         assert_eq!(schema.simple_types[0].restrictions.len(), 1);
@@ -275,14 +485,14 @@ mod tests {
             // Assert that the ComplexTypes declaring arrays are typed.
             if complex.name.starts_with("ArrayOf") {
                 assert_eq!(complex.sequences.len(), 1);
-                assert_eq!(complex.sequences[0].elements.len(), 1);
+                assert_eq!(complex.sequences[0].elements().count(), 1);
+                assert!(complex.array_item().is_some());
             }
         }
 
         for elem in &schema.elements {
             match &elem.kind {
                 Some(Kind::Object(_)) => (),
-                Some(Kind::Array(inner)) if matches!(**inner, Kind::Object(_)) => (),
                 None => (),
                 Some(other) => {
                     assert!(
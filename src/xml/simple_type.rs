@@ -1,28 +1,230 @@
-use crate::{xml, TypeName};
+use crate::{
+    xml, Facets, Implementation, ListImpl, NewtypeImpl, Primitive, TypeName, UnionImpl, WhiteSpace,
+};
 use anyhow::anyhow;
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
 pub struct SimpleType {
+    /// Empty for types defined inline in an element, until they are hoisted.
+    #[serde(default)]
     pub name: String,
 
+    /// The target namespace of the schema defining the type.
+    #[serde(skip, default = "crate::xml::target_namespace")]
+    pub namespace: Option<String>,
+
     #[serde(rename = "restriction", default)]
     pub restrictions: Vec<Restriction>,
+
+    pub list: Option<List>,
+    pub union: Option<Union>,
+}
+
+/// Values that are space separated items of another simple type.
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct List {
+    pub item_type: Option<xml::Kind>,
+
+    /// The item type defined inline instead of referred to by `itemType`.
+    /// It is moved out by `Schema::hoist_anonymous_types`.
+    pub simple_type: Option<Box<SimpleType>>,
+}
+
+/// Values of any of the member types.
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Union {
+    #[serde(default, deserialize_with = "xml::kind::deserialize_kinds")]
+    pub member_types: Vec<xml::Kind>,
+
+    /// Member types defined inline, after those of `memberTypes`.
+    /// They are moved out by `Schema::hoist_anonymous_types`.
+    #[serde(rename = "simpleType", default)]
+    pub simple_types: Vec<SimpleType>,
 }
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
 pub struct Restriction {
     pub base: xml::Kind,
 
     #[serde(rename = "enumeration", default)]
     pub enumerations: Vec<Enumeration>,
+
+    #[serde(rename = "pattern", default)]
+    pub patterns: Vec<Facet>,
+    pub length: Option<Facet>,
+    pub min_length: Option<Facet>,
+    pub max_length: Option<Facet>,
+    pub min_inclusive: Option<Facet>,
+    pub max_inclusive: Option<Facet>,
+    pub total_digits: Option<Facet>,
+    pub fraction_digits: Option<Facet>,
+    pub white_space: Option<Facet>,
 }
 
-#[derive(Debug, serde::Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
 pub struct Enumeration {
     pub value: String,
 }
 
+/// A constraint on the values of a restriction, such as `xs:maxLength`.
+#[derive(Debug, Clone, serde::Deserialize, PartialEq, Eq)]
+pub struct Facet {
+    pub value: String,
+}
+
+impl Restriction {
+    fn facets(self) -> anyhow::Result<Facets> {
+        let number = |facet: Option<Facet>, name: &str| -> anyhow::Result<Option<u64>> {
+            facet
+                .map(|f| {
+                    f.value
+                        .trim()
+                        .parse()
+                        .map_err(|_| anyhow!("Invalid `{}` facet `{}`", name, f.value))
+                })
+                .transpose()
+        };
+
+        let white_space = match self.white_space.as_ref().map(|f| f.value.trim()) {
+            None => None,
+            Some("preserve") => Some(WhiteSpace::Preserve),
+            Some("replace") => Some(WhiteSpace::Replace),
+            Some("collapse") => Some(WhiteSpace::Collapse),
+            Some(other) => return Err(anyhow!("Invalid `whiteSpace` facet `{}`", other)),
+        };
+
+        let base = TypeName::from(self.base);
+
+        Ok(Facets {
+            patterns: self.patterns.into_iter().map(|f| f.value).collect(),
+            length: number(self.length, "length")?,
+            min_length: number(self.min_length, "minLength")?,
+            max_length: number(self.max_length, "maxLength")?,
+            min_inclusive: bound(self.min_inclusive, "minInclusive", &base)?,
+            max_inclusive: bound(self.max_inclusive, "maxInclusive", &base)?,
+            total_digits: number(self.total_digits, "totalDigits")?,
+            fraction_digits: number(self.fraction_digits, "fractionDigits")?,
+            white_space,
+        })
+    }
+}
+
+/// Normalises a bound to a literal of its base type.
+/// Infinite bounds, which bound nothing, are left out.
+fn bound(facet: Option<Facet>, name: &str, base: &TypeName) -> anyhow::Result<Option<String>> {
+    let value = match facet {
+        Some(facet) => facet.value,
+        None => return Ok(None),
+    };
+    let invalid = || anyhow!("Invalid `{}` facet `{}`", name, value);
+    let trimmed = value.trim();
+    // Rust doesn't parse integers with a leading `+`.
+    let number = trimmed.trim_start_matches('+');
+
+    let p = match base {
+        TypeName::Primitive(p) => p,
+        _ => return Ok(Some(trimmed.to_string())),
+    };
+    crate::check_primitive_value(trimmed, p).map_err(|_| invalid())?;
+
+    let literal = match p {
+        Primitive::Float | Primitive::Double | Primitive::Decimal => match number.parse::<f64>() {
+            Ok(n) if n.is_infinite() => return Ok(None),
+            Ok(n) if !n.is_nan() => format!("{:?}", n),
+            _ => return Err(invalid()),
+        },
+        Primitive::Byte
+        | Primitive::Short
+        | Primitive::Int
+        | Primitive::Long
+        | Primitive::Integer
+        | Primitive::NonPositiveInteger
+        | Primitive::NegativeInteger
+        | Primitive::UnsignedByte
+        | Primitive::UnsignedShort
+        | Primitive::UnsignedInt
+        | Primitive::UnsignedLong
+        | Primitive::NonNegativeInteger
+        | Primitive::PositiveInteger => number.parse::<i128>().map_err(|_| invalid())?.to_string(),
+        _ => trimmed.to_string(),
+    };
+
+    Ok(Some(literal))
+}
+
 impl SimpleType {
+    /// The name of the type along with its namespace.
+    pub fn expanded_name(&self) -> xml::ExpandedName {
+        xml::ExpandedName::new(self.namespace.as_deref(), self.name.clone())
+    }
+
+    /// Creates a ListImpl or UnionImpl for lists and unions. Restrictions
+    /// become an EnumImpl if the values are enumerated, otherwise
+    /// a NewtypeImpl checking the facets.
+    pub fn into_implementation(self) -> anyhow::Result<Implementation> {
+        if self.list.is_some() {
+            return self.into_list_impl().map(Implementation::List);
+        }
+        if self.union.is_some() {
+            return self.into_union_impl().map(Implementation::Union);
+        }
+
+        match self.restrictions.first() {
+            Some(r) if !r.enumerations.is_empty() => {
+                self.into_enum_impl().map(Implementation::Enum)
+            }
+            _ => self.into_newtype_impl().map(Implementation::Newtype),
+        }
+    }
+
+    pub fn into_list_impl(self) -> anyhow::Result<ListImpl> {
+        let name = self.name;
+        let item = self
+            .list
+            .and_then(|list| list.item_type)
+            .ok_or_else(|| anyhow!("`{}` has no list with an `itemType`", &name))?;
+
+        Ok(ListImpl {
+            name,
+            roots: Vec::new(),
+            item: TypeName::from(item),
+        })
+    }
+
+    pub fn into_union_impl(self) -> anyhow::Result<UnionImpl> {
+        let name = self.name;
+        let members = self
+            .union
+            .map(|union| union.member_types)
+            .filter(|members| !members.is_empty())
+            .ok_or_else(|| anyhow!("`{}` has no union with `memberTypes`", &name))?;
+
+        Ok(UnionImpl {
+            name,
+            roots: Vec::new(),
+            members: members.into_iter().map(TypeName::from).collect(),
+        })
+    }
+
+    pub fn into_newtype_impl(self) -> anyhow::Result<NewtypeImpl> {
+        let name = self.name;
+        let restriction = self
+            .restrictions
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("`{}` has no restrictions", &name))?;
+
+        Ok(NewtypeImpl {
+            name,
+            roots: Vec::new(),
+            base: TypeName::from(restriction.base.clone()),
+            facets: restriction.facets()?,
+        })
+    }
+
     pub fn into_enum_impl(self) -> anyhow::Result<crate::EnumImpl> {
         let name = self.name;
         let restriction = self
@@ -39,6 +241,7 @@ impl SimpleType {
             .collect();
         Ok(crate::EnumImpl {
             name,
+            roots: Vec::new(),
             base,
             variants,
         })
@@ -82,4 +285,200 @@ mod tests {
 
         assert_eq!(ei.variants, vec!["Unknown", "EventDate", "TransactionDate"]);
     }
+
+    #[test]
+    fn convert_into_newtype_impl() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="Ocr">
+    <xs:restriction base="xs:string">
+      <xs:pattern value="[0-9]+" />
+      <xs:pattern value="[A-Z][0-9]+" />
+      <xs:minLength value="2" />
+      <xs:maxLength value=" 25" />
+      <xs:whiteSpace value="collapse" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Percent">
+    <xs:restriction base="xs:decimal">
+      <xs:minInclusive value="0" />
+      <xs:maxInclusive value="100" />
+      <xs:fractionDigits value="two" />
+    </xs:restriction>
+  </xs:simpleType>
+</xs:schema>
+"#;
+
+        let schema: xml::Schema = xml::de(s.as_bytes()).expect("Deserializing");
+        let mut simple_types = schema.simple_types.into_iter();
+
+        let ocr = simple_types.next().unwrap().into_implementation();
+        assert_eq!(
+            ocr.expect("Converting SimpleType to NewtypeImpl"),
+            Implementation::Newtype(NewtypeImpl {
+                name: "Ocr".into(),
+                roots: Vec::new(),
+                base: TypeName::Primitive(crate::Primitive::String),
+                facets: Facets {
+                    patterns: vec!["[0-9]+".into(), "[A-Z][0-9]+".into()],
+                    min_length: Some(2),
+                    max_length: Some(25),
+                    white_space: Some(WhiteSpace::Collapse),
+                    ..Facets::default()
+                },
+            })
+        );
+
+        let percent = simple_types.next().unwrap().into_newtype_impl();
+        assert_eq!(
+            percent.unwrap_err().to_string(),
+            "Invalid `fractionDigits` facet `two`"
+        );
+    }
+
+    #[test]
+    fn normalize_bounds() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="Count">
+    <xs:restriction base="xs:int">
+      <xs:minInclusive value="+5" />
+      <xs:maxInclusive value=" 10 " />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Big">
+    <xs:restriction base="xs:double">
+      <xs:minInclusive value="1e5" />
+      <xs:maxInclusive value="INF" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Ratio">
+    <xs:restriction base="xs:decimal">
+      <xs:minInclusive value="-.5" />
+      <xs:maxInclusive value="1" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Since">
+    <xs:restriction base="xs:date">
+      <xs:minInclusive value="2020-01-01" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Small">
+    <xs:restriction base="xs:unsignedByte">
+      <xs:minInclusive value="-1" />
+    </xs:restriction>
+  </xs:simpleType>
+  <xs:simpleType name="Broken">
+    <xs:restriction base="xs:float">
+      <xs:maxInclusive value="1,5" />
+    </xs:restriction>
+  </xs:simpleType>
+</xs:schema>
+"#;
+
+        let schema: xml::Schema = xml::de(s.as_bytes()).expect("Deserializing");
+        let bounds = schema
+            .simple_types
+            .into_iter()
+            .map(|st| {
+                st.into_newtype_impl()
+                    .map(|n| (n.facets.min_inclusive, n.facets.max_inclusive))
+                    .map_err(|e| e.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        let some = |s: &str| Some(String::from(s));
+        assert_eq!(
+            bounds,
+            vec![
+                Ok((some("5"), some("10"))),
+                Ok((some("100000.0"), None)),
+                Ok((some("-0.5"), some("1.0"))),
+                Ok((some("2020-01-01"), None)),
+                Err(String::from("Invalid `minInclusive` facet `-1`")),
+                Err(String::from("Invalid `maxInclusive` facet `1,5`")),
+            ]
+        );
+    }
+
+    #[test]
+    fn convert_lists_and_unions() {
+        let s = r#"
+<xs:schema elementFormDefault="qualified" xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="Sizes">
+    <xs:list itemType="Size" />
+  </xs:simpleType>
+  <xs:simpleType name="SizeOrNumber">
+    <xs:union memberTypes="xs:int
+      Size" />
+  </xs:simpleType>
+  <xs:simpleType name="Anonymous">
+    <xs:list>
+      <xs:simpleType>
+        <xs:restriction base="xs:string" />
+      </xs:simpleType>
+    </xs:list>
+  </xs:simpleType>
+  <xs:simpleType name="Code">
+    <xs:union memberTypes="xs:int">
+      <xs:simpleType>
+        <xs:list itemType="xs:int" />
+      </xs:simpleType>
+    </xs:union>
+  </xs:simpleType>
+</xs:schema>
+"#;
+
+        let mut schema: xml::Schema = xml::de(s.as_bytes()).expect("Deserializing");
+        let mut ctx = crate::Context::new(schema.simple_types.iter().map(|st| st.name.clone()));
+        schema.hoist_anonymous_types(&mut ctx);
+        let implementations = schema
+            .simple_types
+            .into_iter()
+            .map(|st| st.into_implementation().map_err(|e| e.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            implementations,
+            vec![
+                Ok(Implementation::List(ListImpl {
+                    name: "Sizes".into(),
+                    roots: Vec::new(),
+                    item: TypeName::object("Size"),
+                })),
+                Ok(Implementation::Union(UnionImpl {
+                    name: "SizeOrNumber".into(),
+                    roots: Vec::new(),
+                    members: vec![
+                        TypeName::Primitive(crate::Primitive::Int),
+                        TypeName::object("Size"),
+                    ],
+                })),
+                Ok(Implementation::List(ListImpl {
+                    name: "Anonymous".into(),
+                    roots: Vec::new(),
+                    item: TypeName::object("AnonymousItem"),
+                })),
+                Ok(Implementation::Union(UnionImpl {
+                    name: "Code".into(),
+                    roots: Vec::new(),
+                    members: vec![
+                        TypeName::Primitive(crate::Primitive::Int),
+                        TypeName::object("CodeMember"),
+                    ],
+                })),
+                Ok(Implementation::Newtype(NewtypeImpl {
+                    name: "AnonymousItem".into(),
+                    roots: Vec::new(),
+                    base: TypeName::Primitive(crate::Primitive::String),
+                    facets: Facets::default(),
+                })),
+                Ok(Implementation::List(ListImpl {
+                    name: "CodeMember".into(),
+                    roots: Vec::new(),
+                    item: TypeName::Primitive(crate::Primitive::Int),
+                })),
+            ]
+        );
+    }
 }